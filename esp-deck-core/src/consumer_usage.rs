@@ -0,0 +1,146 @@
+use serde::{Deserialize, Serialize};
+
+// Names for the usages of the Consumer page (0x0C) most decks need, with spaces
+// dropped from the names in the HID Usage Tables.
+const USAGE_NAMES: &[(u16, &str)] = &[
+    (0x006F, "BrightnessIncrement"),
+    (0x0070, "BrightnessDecrement"),
+    (0x00B0, "Play"),
+    (0x00B1, "Pause"),
+    (0x00B2, "Record"),
+    (0x00B3, "FastForward"),
+    (0x00B4, "Rewind"),
+    (0x00B5, "ScanNextTrack"),
+    (0x00B6, "ScanPreviousTrack"),
+    (0x00B7, "Stop"),
+    (0x00B8, "Eject"),
+    (0x00CD, "PlayPause"),
+    (0x00E2, "Mute"),
+    (0x00E9, "VolumeIncrement"),
+    (0x00EA, "VolumeDecrement"),
+    (0x0183, "ALConsumerControlConfiguration"),
+    (0x018A, "ALEmailReader"),
+    (0x0192, "ALCalculator"),
+    (0x0194, "ALLocalMachineBrowser"),
+    (0x0196, "ALInternetBrowser"),
+    (0x019E, "ALTerminalLockScreensaver"),
+    (0x021F, "ACFind"),
+    (0x0221, "ACSearch"),
+    (0x0223, "ACHome"),
+    (0x0224, "ACBack"),
+    (0x0225, "ACForward"),
+    (0x0226, "ACStop"),
+    (0x0227, "ACRefresh"),
+    (0x022A, "ACBookmarks"),
+    (0x022D, "ACZoomIn"),
+    (0x022E, "ACZoomOut"),
+];
+
+/// A Consumer page usage. Configs may give it as a number or as one of the names
+/// in `USAGE_NAMES`, and known usages are written back by name. Unknown names are
+/// kept as they are, for `Mapper::validate` to report.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(from = "UsageRepr", into = "UsageRepr")]
+pub enum ConsumerUsage {
+    Id(u16),
+    UnknownName(String),
+}
+
+impl ConsumerUsage {
+    /// Looks a usage up by name, ignoring case, spaces, underscores and dashes,
+    /// so "AL Calculator" and "al_calculator" both find ALCalculator.
+    pub fn from_name(name: &str) -> Option<Self> {
+        let wanted: String = name
+            .chars()
+            .filter(|c| !matches!(c, ' ' | '_' | '-'))
+            .collect();
+        USAGE_NAMES
+            .iter()
+            .find(|(_, known)| known.eq_ignore_ascii_case(&wanted))
+            .map(|(id, _)| ConsumerUsage::Id(*id))
+    }
+
+    /// Returns the usage ID, or None for an unknown name.
+    pub fn id(&self) -> Option<u16> {
+        match self {
+            ConsumerUsage::Id(id) => Some(*id),
+            ConsumerUsage::UnknownName(_) => None,
+        }
+    }
+
+    pub fn name(&self) -> Option<&'static str> {
+        let id = self.id()?;
+        USAGE_NAMES
+            .iter()
+            .find(|(known, _)| *known == id)
+            .map(|(_, name)| *name)
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(untagged)]
+enum UsageRepr {
+    Id(u16),
+    Name(String),
+}
+
+impl From<UsageRepr> for ConsumerUsage {
+    fn from(repr: UsageRepr) -> Self {
+        match repr {
+            UsageRepr::Id(id) => ConsumerUsage::Id(id),
+            UsageRepr::Name(name) => {
+                ConsumerUsage::from_name(&name).unwrap_or(ConsumerUsage::UnknownName(name))
+            }
+        }
+    }
+}
+
+impl From<ConsumerUsage> for UsageRepr {
+    fn from(usage: ConsumerUsage) -> Self {
+        match (usage.name(), usage) {
+            (Some(name), _) => UsageRepr::Name(name.to_string()),
+            (None, ConsumerUsage::Id(id)) => UsageRepr::Id(id),
+            (None, ConsumerUsage::UnknownName(name)) => UsageRepr::Name(name),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(json: &str) -> ConsumerUsage {
+        serde_json::from_str(json).unwrap()
+    }
+
+    #[test]
+    fn names_are_matched_loosely() {
+        assert_eq!(parse(r#""VolumeIncrement""#), ConsumerUsage::Id(0xE9));
+        assert_eq!(parse(r#""AL Calculator""#), ConsumerUsage::Id(0x192));
+        assert_eq!(parse(r#""play_pause""#), ConsumerUsage::Id(0xCD));
+    }
+
+    #[test]
+    fn ids_are_kept() {
+        assert_eq!(parse("233"), ConsumerUsage::Id(0xE9));
+        assert_eq!(parse("5"), ConsumerUsage::Id(5));
+    }
+
+    #[test]
+    fn unknown_names_deserialize() {
+        let usage = parse(r#""Nope""#);
+        assert_eq!(usage, ConsumerUsage::UnknownName("Nope".to_string()));
+        assert_eq!(usage.id(), None);
+    }
+
+    #[test]
+    fn known_usages_are_written_by_name() {
+        let write = |usage: ConsumerUsage| serde_json::to_string(&usage).unwrap();
+        assert_eq!(write(ConsumerUsage::Id(0xE9)), r#""VolumeIncrement""#);
+        assert_eq!(write(ConsumerUsage::Id(5)), "5");
+        assert_eq!(
+            write(ConsumerUsage::UnknownName("Nope".to_string())),
+            r#""Nope""#
+        );
+    }
+}
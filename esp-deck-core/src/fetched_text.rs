@@ -0,0 +1,126 @@
+use serde_json::Value;
+use thiserror::Error;
+
+/// Largest response TypeFromUrl reads before giving up.
+pub const MAX_RESPONSE_LEN: usize = 16 * 1024;
+/// Longest text TypeFromUrl types. Longer text is refused rather than typed in part.
+pub const MAX_TYPED_CHARS: usize = 256;
+
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum FetchedTextError {
+    #[error("response is not UTF-8 text")]
+    NotText,
+    #[error("response is not JSON: {0}")]
+    NotJson(String),
+    #[error("nothing at '{0}' in the response")]
+    NothingAtPointer(String),
+    #[error("{0} characters is more than the {max} that can be typed", max = MAX_TYPED_CHARS)]
+    TooLong(usize),
+}
+
+/// Looks a value up by JSON pointer (RFC 6901, like `/data/0/name`) in a JSON response.
+pub fn json_value_at(text: &str, pointer: &str) -> Result<Option<Value>, serde_json::Error> {
+    let json = serde_json::from_str::<Value>(text)?;
+    Ok(json.pointer(pointer).cloned())
+}
+
+/// Picks the text to type out of a response: the value at `json_pointer` if one is
+/// given, or else the whole body. JSON strings are typed without their quotes, and
+/// surrounding whitespace, like the newline most APIs end with, is dropped.
+pub fn text_to_type(body: &[u8], json_pointer: Option<&str>) -> Result<String, FetchedTextError> {
+    let body = std::str::from_utf8(body).map_err(|_| FetchedTextError::NotText)?;
+    let text = match json_pointer {
+        Some(pointer) => {
+            match json_value_at(body, pointer)
+                .map_err(|e| FetchedTextError::NotJson(e.to_string()))?
+            {
+                Some(Value::String(text)) => text,
+                Some(value) => value.to_string(),
+                None => return Err(FetchedTextError::NothingAtPointer(pointer.to_string())),
+            }
+        }
+        None => body.to_string(),
+    };
+    let text = text.trim();
+    let len = text.chars().count();
+    if len > MAX_TYPED_CHARS {
+        return Err(FetchedTextError::TooLong(len));
+    }
+    Ok(text.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn whole_body_is_trimmed() {
+        assert_eq!(
+            text_to_type(b"  abc 123\r\n", None),
+            Ok("abc 123".to_string())
+        );
+        assert_eq!(text_to_type(b"\n", None), Ok(String::new()));
+    }
+
+    #[test]
+    fn json_strings_lose_their_quotes() {
+        let body = br#"{"data": [{"name": " x y\n"}]}"#;
+        assert_eq!(
+            text_to_type(body, Some("/data/0/name")),
+            Ok("x y".to_string())
+        );
+    }
+
+    #[test]
+    fn other_json_values_are_typed_as_json() {
+        let body = br#"{"count": 7, "ok": true, "list": [1, 2]}"#;
+        assert_eq!(text_to_type(body, Some("/count")), Ok("7".to_string()));
+        assert_eq!(text_to_type(body, Some("/ok")), Ok("true".to_string()));
+        assert_eq!(text_to_type(body, Some("/list")), Ok("[1,2]".to_string()));
+    }
+
+    #[test]
+    fn pointer_miss() {
+        assert_eq!(
+            text_to_type(br#"{"a": 1}"#, Some("/b")),
+            Err(FetchedTextError::NothingAtPointer("/b".to_string()))
+        );
+        // Pointers have to start with a slash
+        assert_eq!(
+            text_to_type(br#"{"a": 1}"#, Some("a")),
+            Err(FetchedTextError::NothingAtPointer("a".to_string()))
+        );
+    }
+
+    #[test]
+    fn not_json() {
+        assert!(matches!(
+            text_to_type(b"<html>", Some("/a")),
+            Err(FetchedTextError::NotJson(_))
+        ));
+        // Without a pointer the body isn't parsed
+        assert_eq!(text_to_type(b"<html>", None), Ok("<html>".to_string()));
+    }
+
+    #[test]
+    fn not_text() {
+        assert_eq!(
+            text_to_type(&[0x61, 0xFF, 0x62], None),
+            Err(FetchedTextError::NotText)
+        );
+    }
+
+    #[test]
+    fn length_limit_counts_characters() {
+        let limit = "é".repeat(MAX_TYPED_CHARS);
+        assert_eq!(text_to_type(limit.as_bytes(), None), Ok(limit.clone()));
+        let over = format!("{}a", limit);
+        assert_eq!(
+            text_to_type(over.as_bytes(), None),
+            Err(FetchedTextError::TooLong(MAX_TYPED_CHARS + 1))
+        );
+        // Only what's left after trimming counts
+        let padded = format!("  {}\n", "a".repeat(MAX_TYPED_CHARS));
+        assert!(text_to_type(padded.as_bytes(), None).is_ok());
+    }
+}
@@ -0,0 +1,280 @@
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+pub const DEFAULT_LONG_PRESS_MS: u64 = 500;
+pub const DEFAULT_DOUBLE_TAP_MS: u64 = 300;
+
+/// Gestures that can be recognised on a single button.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Gesture {
+    Tap,
+    LongPress,
+    DoubleTap,
+}
+
+/// Which gestures a button has actions bound to. Gestures without a binding are
+/// never reported, so a button with no double tap binding doesn't have to wait
+/// for a possible second tap before firing.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct GestureBindings {
+    pub long_press: bool,
+    pub double_tap: bool,
+}
+
+impl GestureBindings {
+    pub fn any(&self) -> bool {
+        self.long_press || self.double_tap
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GestureTiming {
+    /// How long a button must stay down to count as a long press
+    pub long_press: Duration,
+    /// Maximum time between the first release and the second press of a double tap
+    pub double_tap: Duration,
+}
+
+impl Default for GestureTiming {
+    fn default() -> Self {
+        Self {
+            long_press: Duration::from_millis(DEFAULT_LONG_PRESS_MS),
+            double_tap: Duration::from_millis(DEFAULT_DOUBLE_TAP_MS),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+enum ButtonState {
+    Down {
+        since: Instant,
+        second_tap: bool,
+        bindings: GestureBindings,
+    },
+    LongPressed,
+    AwaitingSecondTap {
+        released_at: Instant,
+    },
+}
+
+/// Classifies timestamped press/release events into taps, long presses and double taps.
+///
+/// `press` and `release` are fed as events come in, and `poll` must be called once the
+/// deadline returned by `next_deadline` has passed, to report gestures that are
+/// recognised purely by time (long presses, and taps that didn't turn into double taps).
+#[derive(Debug, Default)]
+pub struct GestureDetector {
+    timing: GestureTiming,
+    buttons: HashMap<i32, ButtonState>,
+}
+
+impl GestureDetector {
+    pub fn new(timing: GestureTiming) -> Self {
+        Self {
+            timing,
+            buttons: HashMap::new(),
+        }
+    }
+
+    pub fn set_timing(&mut self, timing: GestureTiming) {
+        self.timing = timing;
+    }
+
+    /// Returns true if the detector has a pending press or tap for this button.
+    pub fn is_tracking(&self, button_id: i32) -> bool {
+        self.buttons.contains_key(&button_id)
+    }
+
+    pub fn press(&mut self, button_id: i32, bindings: GestureBindings, now: Instant) {
+        let second_tap = matches!(
+            self.buttons.get(&button_id),
+            Some(ButtonState::AwaitingSecondTap { .. })
+        );
+        self.buttons.insert(
+            button_id,
+            ButtonState::Down {
+                since: now,
+                second_tap,
+                bindings,
+            },
+        );
+    }
+
+    pub fn release(&mut self, button_id: i32, now: Instant) -> Option<Gesture> {
+        match self.buttons.remove(&button_id)? {
+            ButtonState::Down {
+                second_tap: true, ..
+            } => Some(Gesture::DoubleTap),
+            ButtonState::Down { bindings, .. } if bindings.double_tap => {
+                self.buttons.insert(
+                    button_id,
+                    ButtonState::AwaitingSecondTap { released_at: now },
+                );
+                None
+            }
+            ButtonState::Down { .. } => Some(Gesture::Tap),
+            ButtonState::LongPressed => None,
+            ButtonState::AwaitingSecondTap { .. } => {
+                log::warn!("Button {} released without being pressed", button_id);
+                None
+            }
+        }
+    }
+
+    /// Reports gestures whose time threshold has passed by `now`.
+    pub fn poll(&mut self, now: Instant) -> Vec<(i32, Gesture)> {
+        let mut gestures = Vec::new();
+        for (button_id, state) in self.buttons.iter_mut() {
+            match *state {
+                ButtonState::Down {
+                    since,
+                    second_tap: false,
+                    bindings,
+                } if bindings.long_press && now >= since + self.timing.long_press => {
+                    *state = ButtonState::LongPressed;
+                    gestures.push((*button_id, Gesture::LongPress));
+                }
+                ButtonState::AwaitingSecondTap { released_at }
+                    if now >= released_at + self.timing.double_tap =>
+                {
+                    gestures.push((*button_id, Gesture::Tap));
+                }
+                _ => {}
+            }
+        }
+        for (button_id, gesture) in &gestures {
+            if *gesture == Gesture::Tap {
+                self.buttons.remove(button_id);
+            }
+        }
+        gestures
+    }
+
+    /// Returns the earliest instant at which `poll` may report a gesture.
+    pub fn next_deadline(&self) -> Option<Instant> {
+        self.buttons
+            .values()
+            .filter_map(|state| match *state {
+                ButtonState::Down {
+                    since,
+                    second_tap: false,
+                    bindings,
+                } if bindings.long_press => Some(since + self.timing.long_press),
+                ButtonState::AwaitingSecondTap { released_at } => {
+                    Some(released_at + self.timing.double_tap)
+                }
+                _ => None,
+            })
+            .min()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BOTH: GestureBindings = GestureBindings {
+        long_press: true,
+        double_tap: true,
+    };
+
+    fn ms(ms: u64) -> Duration {
+        Duration::from_millis(ms)
+    }
+
+    #[test]
+    fn tap_without_double_tap_binding_fires_on_release() {
+        let mut detector = GestureDetector::default();
+        let start = Instant::now();
+        let bindings = GestureBindings {
+            long_press: true,
+            double_tap: false,
+        };
+        detector.press(1, bindings, start);
+        assert_eq!(detector.release(1, start + ms(100)), Some(Gesture::Tap));
+        assert!(!detector.is_tracking(1));
+    }
+
+    #[test]
+    fn tap_waits_out_the_double_tap_window() {
+        let mut detector = GestureDetector::default();
+        let start = Instant::now();
+        detector.press(1, BOTH, start);
+        assert_eq!(detector.release(1, start + ms(100)), None);
+        assert_eq!(detector.next_deadline(), Some(start + ms(400)));
+        assert!(detector.poll(start + ms(399)).is_empty());
+        assert_eq!(detector.poll(start + ms(400)), vec![(1, Gesture::Tap)]);
+        assert!(!detector.is_tracking(1));
+    }
+
+    #[test]
+    fn double_tap_inside_window() {
+        let mut detector = GestureDetector::default();
+        let start = Instant::now();
+        detector.press(1, BOTH, start);
+        detector.release(1, start + ms(50));
+        assert!(detector.poll(start + ms(300)).is_empty());
+        detector.press(1, BOTH, start + ms(300));
+        assert_eq!(
+            detector.release(1, start + ms(350)),
+            Some(Gesture::DoubleTap)
+        );
+        assert!(!detector.is_tracking(1));
+    }
+
+    #[test]
+    fn second_press_outside_window_starts_a_new_tap() {
+        let mut detector = GestureDetector::default();
+        let start = Instant::now();
+        detector.press(1, BOTH, start);
+        detector.release(1, start + ms(50));
+        assert_eq!(detector.poll(start + ms(351)), vec![(1, Gesture::Tap)]);
+        detector.press(1, BOTH, start + ms(400));
+        assert_eq!(detector.release(1, start + ms(450)), None);
+        assert_eq!(detector.poll(start + ms(750)), vec![(1, Gesture::Tap)]);
+    }
+
+    #[test]
+    fn long_press_at_threshold() {
+        let mut detector = GestureDetector::default();
+        let start = Instant::now();
+        detector.press(1, BOTH, start);
+        assert_eq!(detector.next_deadline(), Some(start + ms(500)));
+        assert!(detector.poll(start + ms(499)).is_empty());
+        assert_eq!(
+            detector.poll(start + ms(500)),
+            vec![(1, Gesture::LongPress)]
+        );
+        assert!(detector.poll(start + ms(900)).is_empty());
+        assert_eq!(detector.release(1, start + ms(1000)), None);
+        assert!(!detector.is_tracking(1));
+    }
+
+    #[test]
+    fn press_during_pending_double_tap_is_not_a_long_press() {
+        let mut detector = GestureDetector::default();
+        let start = Instant::now();
+        detector.press(1, BOTH, start);
+        detector.release(1, start + ms(50));
+        detector.press(1, BOTH, start + ms(100));
+        // The second press of a double tap has no long press deadline
+        assert_eq!(detector.next_deadline(), None);
+        assert!(detector.poll(start + ms(2000)).is_empty());
+        assert_eq!(
+            detector.release(1, start + ms(2100)),
+            Some(Gesture::DoubleTap)
+        );
+    }
+
+    #[test]
+    fn buttons_are_tracked_separately() {
+        let mut detector = GestureDetector::default();
+        let start = Instant::now();
+        detector.press(1, BOTH, start);
+        detector.release(1, start + ms(50));
+        detector.press(2, BOTH, start + ms(100));
+        assert_eq!(detector.release(2, start + ms(150)), None);
+        assert_eq!(detector.poll(start + ms(350)), vec![(1, Gesture::Tap)]);
+        assert!(detector.is_tracking(2));
+    }
+}
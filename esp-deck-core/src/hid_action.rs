@@ -0,0 +1,59 @@
+use crate::midi::MidiMessage;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum HttpMethod {
+    #[default]
+    Get,
+    Post,
+    Put,
+    Patch,
+    Delete,
+}
+
+/// An HTTP request for the Actor to hand to the HttpClientPool.
+#[derive(Debug, Clone, PartialEq)]
+pub struct WebRequest {
+    pub method: HttpMethod,
+    pub url: String,
+    pub headers: Vec<(String, String)>,
+    pub body: Option<String>,
+}
+
+/// Text to fetch for a TypeFromUrl action.
+#[derive(Debug, Clone, PartialEq)]
+pub struct UrlText {
+    pub url: String,
+    pub json_pointer: Option<String>,
+    pub layout: Option<String>,
+}
+
+// Represents a single primitive HID action or delay
+#[derive(Debug, Clone, PartialEq)]
+pub enum HidAction {
+    KeyPress(u8, Vec<u8>),      // modifier, keycodes
+    KeyRelease,                 // Releases all keys/modifiers
+    MouseMove(i8, i8),          // dx, dy
+    MouseMoveTo(u16, u16),      // x, y on the absolute pointer, 0 to ABSOLUTE_MOUSE_MAX
+    MousePress(u8),             // buttons bitmask
+    MouseRelease,               // Releases all buttons
+    MouseWheel(i8),             // wheel movement
+    MouseHScroll(i8),           // horizontal scroll movement, positive is right
+    ConsumerPress(u16),         // usage_id
+    ConsumerRelease,            // Releases consumer control
+    SystemPress(u8),            // System Control report value (1: Power Down, 2: Sleep, 3: Wake Up)
+    SystemRelease,              // Releases system control
+    GamepadPress(u8),           // gamepad button, 1 to GAMEPAD_BUTTONS
+    GamepadRelease(Option<u8>), // gamepad button, or None to release all buttons and center everything
+    GamepadAxis(u8, i8),        // axis (0: X, 1: Y), position
+    GamepadHat(u8),             // hat direction, or GAMEPAD_HAT_CENTERED
+    Midi(MidiMessage),          // MIDI message sent on the USB MIDI interface
+    RawReport(Vec<u8>),         // bytes of the raw vendor report, zero padded when sent
+    HttpRequest(WebRequest),    // sent in the background, the result goes to the status log
+    Udp(String, u16, Vec<u8>),  // host, port, datagram
+    TypeFromUrl(UrlText),       // fetched in the background, typed once the button is pressed again
+    Delay(Duration),            // Pause execution
+    StopMacros(Option<i32>),    // Cancels the running macros of a button, or of all other buttons
+}
@@ -0,0 +1,10 @@
+use serde::{Deserialize, Serialize};
+
+/// Operating system of the host the device is plugged into, for actions that
+/// need to be typed differently on each.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HostOs {
+    Linux,
+    Windows,
+    MacOs,
+}
@@ -0,0 +1,513 @@
+use keycode::{KeyMap, KeyMappingCode};
+use std::fmt;
+use std::str::FromStr;
+use thiserror::Error;
+
+const NONE: u8 = 0x00;
+const SHIFT: u8 = 0x02; // Left Shift
+const ALTGR: u8 = 0x40; // Right Alt
+
+const LETTERS: [KeyMappingCode; 26] = [
+    KeyMappingCode::KeyA,
+    KeyMappingCode::KeyB,
+    KeyMappingCode::KeyC,
+    KeyMappingCode::KeyD,
+    KeyMappingCode::KeyE,
+    KeyMappingCode::KeyF,
+    KeyMappingCode::KeyG,
+    KeyMappingCode::KeyH,
+    KeyMappingCode::KeyI,
+    KeyMappingCode::KeyJ,
+    KeyMappingCode::KeyK,
+    KeyMappingCode::KeyL,
+    KeyMappingCode::KeyM,
+    KeyMappingCode::KeyN,
+    KeyMappingCode::KeyO,
+    KeyMappingCode::KeyP,
+    KeyMappingCode::KeyQ,
+    KeyMappingCode::KeyR,
+    KeyMappingCode::KeyS,
+    KeyMappingCode::KeyT,
+    KeyMappingCode::KeyU,
+    KeyMappingCode::KeyV,
+    KeyMappingCode::KeyW,
+    KeyMappingCode::KeyX,
+    KeyMappingCode::KeyY,
+    KeyMappingCode::KeyZ,
+];
+
+const DIGITS: [KeyMappingCode; 10] = [
+    KeyMappingCode::Digit0,
+    KeyMappingCode::Digit1,
+    KeyMappingCode::Digit2,
+    KeyMappingCode::Digit3,
+    KeyMappingCode::Digit4,
+    KeyMappingCode::Digit5,
+    KeyMappingCode::Digit6,
+    KeyMappingCode::Digit7,
+    KeyMappingCode::Digit8,
+    KeyMappingCode::Digit9,
+];
+
+/// Host keyboard layouts that text can be typed for. The device only sends key
+/// positions, so the host's layout decides which character each key produces.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum KeyboardLayout {
+    #[default]
+    Us,
+    Uk,
+    De,
+    Fr,
+}
+
+/// A single key, with the modifiers held while pressing it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KeyStroke {
+    pub modifier: u8,
+    pub keycode: u8,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum LayoutError {
+    #[error("Unknown keyboard layout '{0}'")]
+    UnknownLayout(String),
+    #[error("The {layout} keyboard layout can't type {chars:?}")]
+    UnsupportedChars {
+        layout: KeyboardLayout,
+        chars: Vec<char>,
+    },
+}
+
+// Position of a character on a layout. Dead keys don't produce anything on their
+// own, so they are followed by a Space to get the character itself.
+#[derive(Clone, Copy)]
+struct Key {
+    code: KeyMappingCode,
+    modifier: u8,
+    dead: bool,
+}
+
+fn key(code: KeyMappingCode, modifier: u8) -> Option<Key> {
+    Some(Key {
+        code,
+        modifier,
+        dead: false,
+    })
+}
+
+fn dead_key(code: KeyMappingCode, modifier: u8) -> Option<Key> {
+    Some(Key {
+        code,
+        modifier,
+        dead: true,
+    })
+}
+
+fn keycode(code: KeyMappingCode) -> u8 {
+    KeyMap::from(code).usb as u8
+}
+
+impl KeyboardLayout {
+    pub fn name(&self) -> &'static str {
+        match self {
+            KeyboardLayout::Us => "US",
+            KeyboardLayout::Uk => "UK",
+            KeyboardLayout::De => "DE",
+            KeyboardLayout::Fr => "FR",
+        }
+    }
+
+    /// Parses an optional layout name, falling back to the US layout when none is given.
+    pub fn from_name(name: Option<&str>) -> Result<Self, LayoutError> {
+        name.map(Self::from_str)
+            .transpose()
+            .map(Option::unwrap_or_default)
+    }
+
+    /// Returns the key strokes that type `c`, or None if the layout can't produce it.
+    pub fn strokes_for(&self, c: char) -> Option<Vec<KeyStroke>> {
+        let key = self.find_key(c)?;
+        let mut strokes = vec![KeyStroke {
+            modifier: key.modifier,
+            keycode: keycode(key.code),
+        }];
+        if key.dead {
+            strokes.push(KeyStroke {
+                modifier: NONE,
+                keycode: keycode(KeyMappingCode::Space),
+            });
+        }
+        Some(strokes)
+    }
+
+    /// Translates the whole text into key strokes. Fails with all the characters the
+    /// layout can't produce, rather than typing the text with some of them missing.
+    pub fn translate(&self, text: &str) -> Result<Vec<KeyStroke>, LayoutError> {
+        let mut strokes = Vec::new();
+        let mut unsupported: Vec<char> = Vec::new();
+        for c in text.chars() {
+            match self.strokes_for(c) {
+                Some(key_strokes) => strokes.extend(key_strokes),
+                None => {
+                    if !unsupported.contains(&c) {
+                        unsupported.push(c);
+                    }
+                }
+            }
+        }
+        if unsupported.is_empty() {
+            Ok(strokes)
+        } else {
+            Err(LayoutError::UnsupportedChars {
+                layout: *self,
+                chars: unsupported,
+            })
+        }
+    }
+
+    fn find_key(&self, c: char) -> Option<Key> {
+        match c {
+            ' ' => return key(KeyMappingCode::Space, NONE),
+            '\n' => return key(KeyMappingCode::Enter, NONE),
+            '\t' => return key(KeyMappingCode::Tab, NONE),
+            'a'..='z' => return key(self.letter(c), NONE),
+            'A'..='Z' => return key(self.letter(c.to_ascii_lowercase()), SHIFT),
+            _ => {}
+        }
+        match self {
+            KeyboardLayout::Us => Self::us_symbol(c),
+            KeyboardLayout::Uk => Self::uk_symbol(c),
+            KeyboardLayout::De => Self::de_symbol(c),
+            KeyboardLayout::Fr => Self::fr_symbol(c),
+        }
+    }
+
+    // Key for a lowercase ASCII letter
+    fn letter(&self, c: char) -> KeyMappingCode {
+        let position = match (self, c) {
+            (KeyboardLayout::De, 'y') => 'z',
+            (KeyboardLayout::De, 'z') => 'y',
+            (KeyboardLayout::Fr, 'a') => 'q',
+            (KeyboardLayout::Fr, 'q') => 'a',
+            (KeyboardLayout::Fr, 'z') => 'w',
+            (KeyboardLayout::Fr, 'w') => 'z',
+            (KeyboardLayout::Fr, 'm') => return KeyMappingCode::Semicolon,
+            _ => c,
+        };
+        LETTERS[(position as u8 - b'a') as usize]
+    }
+
+    fn digit(c: char) -> KeyMappingCode {
+        DIGITS[(c as u8 - b'0') as usize]
+    }
+
+    fn us_symbol(c: char) -> Option<Key> {
+        use KeyMappingCode::*;
+        match c {
+            '0'..='9' => key(Self::digit(c), NONE),
+            '!' => key(Digit1, SHIFT),
+            '@' => key(Digit2, SHIFT),
+            '#' => key(Digit3, SHIFT),
+            '$' => key(Digit4, SHIFT),
+            '%' => key(Digit5, SHIFT),
+            '^' => key(Digit6, SHIFT),
+            '&' => key(Digit7, SHIFT),
+            '*' => key(Digit8, SHIFT),
+            '(' => key(Digit9, SHIFT),
+            ')' => key(Digit0, SHIFT),
+            '`' => key(Backquote, NONE),
+            '~' => key(Backquote, SHIFT),
+            '-' => key(Minus, NONE),
+            '_' => key(Minus, SHIFT),
+            '=' => key(Equal, NONE),
+            '+' => key(Equal, SHIFT),
+            '[' => key(BracketLeft, NONE),
+            '{' => key(BracketLeft, SHIFT),
+            ']' => key(BracketRight, NONE),
+            '}' => key(BracketRight, SHIFT),
+            '\\' => key(Backslash, NONE),
+            '|' => key(Backslash, SHIFT),
+            ';' => key(Semicolon, NONE),
+            ':' => key(Semicolon, SHIFT),
+            '\'' => key(Quote, NONE),
+            '"' => key(Quote, SHIFT),
+            ',' => key(Comma, NONE),
+            '<' => key(Comma, SHIFT),
+            '.' => key(Period, NONE),
+            '>' => key(Period, SHIFT),
+            '/' => key(Slash, NONE),
+            '?' => key(Slash, SHIFT),
+            _ => None,
+        }
+    }
+
+    fn uk_symbol(c: char) -> Option<Key> {
+        use KeyMappingCode::*;
+        match c {
+            '0'..='9' => key(Self::digit(c), NONE),
+            '!' => key(Digit1, SHIFT),
+            '"' => key(Digit2, SHIFT),
+            '£' => key(Digit3, SHIFT),
+            '$' => key(Digit4, SHIFT),
+            '€' => key(Digit4, ALTGR),
+            '%' => key(Digit5, SHIFT),
+            '^' => key(Digit6, SHIFT),
+            '&' => key(Digit7, SHIFT),
+            '*' => key(Digit8, SHIFT),
+            '(' => key(Digit9, SHIFT),
+            ')' => key(Digit0, SHIFT),
+            '`' => key(Backquote, NONE),
+            '¬' => key(Backquote, SHIFT),
+            '¦' => key(Backquote, ALTGR),
+            '-' => key(Minus, NONE),
+            '_' => key(Minus, SHIFT),
+            '=' => key(Equal, NONE),
+            '+' => key(Equal, SHIFT),
+            '[' => key(BracketLeft, NONE),
+            '{' => key(BracketLeft, SHIFT),
+            ']' => key(BracketRight, NONE),
+            '}' => key(BracketRight, SHIFT),
+            ';' => key(Semicolon, NONE),
+            ':' => key(Semicolon, SHIFT),
+            '\'' => key(Quote, NONE),
+            '@' => key(Quote, SHIFT),
+            '#' => key(IntlHash, NONE),
+            '~' => key(IntlHash, SHIFT),
+            '\\' => key(IntlBackslash, NONE),
+            '|' => key(IntlBackslash, SHIFT),
+            ',' => key(Comma, NONE),
+            '<' => key(Comma, SHIFT),
+            '.' => key(Period, NONE),
+            '>' => key(Period, SHIFT),
+            '/' => key(Slash, NONE),
+            '?' => key(Slash, SHIFT),
+            _ => None,
+        }
+    }
+
+    fn de_symbol(c: char) -> Option<Key> {
+        use KeyMappingCode::*;
+        match c {
+            '0'..='9' => key(Self::digit(c), NONE),
+            '^' => dead_key(Backquote, NONE),
+            '°' => key(Backquote, SHIFT),
+            '!' => key(Digit1, SHIFT),
+            '"' => key(Digit2, SHIFT),
+            '²' => key(Digit2, ALTGR),
+            '§' => key(Digit3, SHIFT),
+            '³' => key(Digit3, ALTGR),
+            '$' => key(Digit4, SHIFT),
+            '%' => key(Digit5, SHIFT),
+            '&' => key(Digit6, SHIFT),
+            '/' => key(Digit7, SHIFT),
+            '{' => key(Digit7, ALTGR),
+            '(' => key(Digit8, SHIFT),
+            '[' => key(Digit8, ALTGR),
+            ')' => key(Digit9, SHIFT),
+            ']' => key(Digit9, ALTGR),
+            '=' => key(Digit0, SHIFT),
+            '}' => key(Digit0, ALTGR),
+            'ß' => key(Minus, NONE),
+            '?' => key(Minus, SHIFT),
+            '\\' => key(Minus, ALTGR),
+            '´' => dead_key(Equal, NONE),
+            '`' => dead_key(Equal, SHIFT),
+            '@' => key(KeyQ, ALTGR),
+            '€' => key(KeyE, ALTGR),
+            'µ' => key(KeyM, ALTGR),
+            'ü' => key(BracketLeft, NONE),
+            'Ü' => key(BracketLeft, SHIFT),
+            '+' => key(BracketRight, NONE),
+            '*' => key(BracketRight, SHIFT),
+            '~' => key(BracketRight, ALTGR),
+            'ö' => key(Semicolon, NONE),
+            'Ö' => key(Semicolon, SHIFT),
+            'ä' => key(Quote, NONE),
+            'Ä' => key(Quote, SHIFT),
+            '#' => key(IntlHash, NONE),
+            '\'' => key(IntlHash, SHIFT),
+            '<' => key(IntlBackslash, NONE),
+            '>' => key(IntlBackslash, SHIFT),
+            '|' => key(IntlBackslash, ALTGR),
+            ',' => key(Comma, NONE),
+            ';' => key(Comma, SHIFT),
+            '.' => key(Period, NONE),
+            ':' => key(Period, SHIFT),
+            '-' => key(Slash, NONE),
+            '_' => key(Slash, SHIFT),
+            _ => None,
+        }
+    }
+
+    fn fr_symbol(c: char) -> Option<Key> {
+        use KeyMappingCode::*;
+        match c {
+            // Digits need Shift on AZERTY
+            '0'..='9' => key(Self::digit(c), SHIFT),
+            '²' => key(Backquote, NONE),
+            '&' => key(Digit1, NONE),
+            'é' => key(Digit2, NONE),
+            '~' => dead_key(Digit2, ALTGR),
+            '"' => key(Digit3, NONE),
+            '#' => key(Digit3, ALTGR),
+            '\'' => key(Digit4, NONE),
+            '{' => key(Digit4, ALTGR),
+            '(' => key(Digit5, NONE),
+            '[' => key(Digit5, ALTGR),
+            '-' => key(Digit6, NONE),
+            '|' => key(Digit6, ALTGR),
+            'è' => key(Digit7, NONE),
+            '`' => dead_key(Digit7, ALTGR),
+            '_' => key(Digit8, NONE),
+            '\\' => key(Digit8, ALTGR),
+            'ç' => key(Digit9, NONE),
+            '^' => key(Digit9, ALTGR),
+            'à' => key(Digit0, NONE),
+            '@' => key(Digit0, ALTGR),
+            ')' => key(Minus, NONE),
+            '°' => key(Minus, SHIFT),
+            ']' => key(Minus, ALTGR),
+            '=' => key(Equal, NONE),
+            '+' => key(Equal, SHIFT),
+            '}' => key(Equal, ALTGR),
+            '€' => key(KeyE, ALTGR),
+            '¨' => dead_key(BracketLeft, SHIFT),
+            '$' => key(BracketRight, NONE),
+            '£' => key(BracketRight, SHIFT),
+            '¤' => key(BracketRight, ALTGR),
+            'ù' => key(Quote, NONE),
+            '%' => key(Quote, SHIFT),
+            '*' => key(IntlHash, NONE),
+            'µ' => key(IntlHash, SHIFT),
+            '<' => key(IntlBackslash, NONE),
+            '>' => key(IntlBackslash, SHIFT),
+            ',' => key(KeyM, NONE),
+            '?' => key(KeyM, SHIFT),
+            ';' => key(Comma, NONE),
+            '.' => key(Comma, SHIFT),
+            ':' => key(Period, NONE),
+            '/' => key(Period, SHIFT),
+            '!' => key(Slash, NONE),
+            '§' => key(Slash, SHIFT),
+            _ => None,
+        }
+    }
+}
+
+impl FromStr for KeyboardLayout {
+    type Err = LayoutError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_uppercase().as_str() {
+            "US" | "EN-US" => Ok(KeyboardLayout::Us),
+            "UK" | "GB" | "EN-GB" => Ok(KeyboardLayout::Uk),
+            "DE" | "DE-DE" => Ok(KeyboardLayout::De),
+            "FR" | "FR-FR" => Ok(KeyboardLayout::Fr),
+            _ => Err(LayoutError::UnknownLayout(s.to_string())),
+        }
+    }
+}
+
+impl fmt::Display for KeyboardLayout {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use KeyMappingCode::*;
+
+    fn stroke(code: KeyMappingCode, modifier: u8) -> KeyStroke {
+        KeyStroke {
+            modifier,
+            keycode: keycode(code),
+        }
+    }
+
+    fn typed(layout: KeyboardLayout, text: &str) -> Vec<KeyStroke> {
+        layout.translate(text).unwrap()
+    }
+
+    #[test]
+    fn us_letters_digits_and_symbols() {
+        assert_eq!(
+            typed(KeyboardLayout::Us, "aZ1@"),
+            vec![
+                stroke(KeyA, NONE),
+                stroke(KeyZ, SHIFT),
+                stroke(Digit1, NONE),
+                stroke(Digit2, SHIFT),
+            ]
+        );
+    }
+
+    #[test]
+    fn uk_symbols() {
+        let uk = KeyboardLayout::Uk;
+        assert_eq!(typed(uk, "£"), vec![stroke(Digit3, SHIFT)]);
+        // '"' and '@' swap places compared to US
+        assert_eq!(typed(uk, "\""), vec![stroke(Digit2, SHIFT)]);
+        assert_eq!(typed(uk, "@"), vec![stroke(Quote, SHIFT)]);
+        assert_eq!(typed(uk, "#"), vec![stroke(IntlHash, NONE)]);
+    }
+
+    #[test]
+    fn de_symbols() {
+        let de = KeyboardLayout::De;
+        assert_eq!(
+            typed(de, "zy"),
+            vec![stroke(KeyY, NONE), stroke(KeyZ, NONE)]
+        );
+        assert_eq!(typed(de, "Z"), vec![stroke(KeyY, SHIFT)]);
+        assert_eq!(typed(de, "ß"), vec![stroke(Minus, NONE)]);
+        assert_eq!(typed(de, "@"), vec![stroke(KeyQ, ALTGR)]);
+        // Dead keys are followed by a space
+        assert_eq!(
+            typed(de, "^"),
+            vec![stroke(Backquote, NONE), stroke(Space, NONE)]
+        );
+    }
+
+    #[test]
+    fn fr_symbols() {
+        let fr = KeyboardLayout::Fr;
+        assert_eq!(
+            typed(fr, "aq"),
+            vec![stroke(KeyQ, NONE), stroke(KeyA, NONE)]
+        );
+        assert_eq!(typed(fr, "m"), vec![stroke(Semicolon, NONE)]);
+        assert_eq!(typed(fr, "M"), vec![stroke(Semicolon, SHIFT)]);
+        assert_eq!(
+            typed(fr, "10"),
+            vec![stroke(Digit1, SHIFT), stroke(Digit0, SHIFT)]
+        );
+        assert_eq!(typed(fr, "&"), vec![stroke(Digit1, NONE)]);
+    }
+
+    #[test]
+    fn unmappable_characters_are_reported() {
+        assert_eq!(
+            KeyboardLayout::Us.translate("a£b£ü"),
+            Err(LayoutError::UnsupportedChars {
+                layout: KeyboardLayout::Us,
+                chars: vec!['£', 'ü'],
+            })
+        );
+        assert_eq!(KeyboardLayout::Uk.strokes_for('ß'), None);
+    }
+
+    #[test]
+    fn layout_names() {
+        assert_eq!(KeyboardLayout::from_name(None), Ok(KeyboardLayout::Us));
+        assert_eq!(
+            KeyboardLayout::from_name(Some("en-gb")),
+            Ok(KeyboardLayout::Uk)
+        );
+        assert_eq!(
+            KeyboardLayout::from_name(Some("xx")),
+            Err(LayoutError::UnknownLayout("xx".to_string()))
+        );
+    }
+}
@@ -0,0 +1,13 @@
+pub mod consumer_usage;
+pub mod fetched_text;
+pub mod gesture;
+pub mod hid_action;
+pub mod host_os;
+pub mod keyboard_layout;
+pub mod macros;
+pub mod midi;
+pub mod osc;
+pub mod text_template;
+pub mod udp;
+pub mod unicode_input;
+pub mod wake_on_lan;
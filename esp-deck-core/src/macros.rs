@@ -0,0 +1,357 @@
+use crate::hid_action::HidAction;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Minimum time between two runs of a button's `while_held` actions or a `RepeatWhileHeld`
+/// loop, so that holding a button without any delays doesn't flood the host with reports.
+pub const HOLD_REPEAT_INTERVAL: Duration = Duration::from_millis(50);
+
+/// A translated macro step. Loops are kept as loops instead of being unrolled
+/// into a flat list, and are only expanded while a `MacroCursor` runs them.
+#[derive(Debug, Clone, PartialEq)]
+pub enum MacroOp {
+    Action(HidAction),
+    Repeat {
+        count: u32,
+        ops: Arc<[MacroOp]>,
+    },
+    RepeatWhileHeld {
+        interval: Duration,
+        ops: Arc<[MacroOp]>,
+    },
+    /// Moves the pointer by any distance, split into MouseMove steps as the macro runs.
+    Glide {
+        dx: i32,
+        dy: i32,
+        duration: Duration,
+    },
+}
+
+impl From<HidAction> for MacroOp {
+    fn from(action: HidAction) -> Self {
+        MacroOp::Action(action)
+    }
+}
+
+const GLIDE_STEP_MS: u64 = 10;
+
+// Splits a move into MouseMove steps that each fit in a mouse report, about
+// GLIDE_STEP_MS apart. Each step goes to the next point on the line, so no distance is lost.
+#[derive(Debug, Clone)]
+struct Glide {
+    dx: i64,
+    dy: i64,
+    steps: i64,
+    step: i64,
+    step_delay: Duration,
+    delay_due: bool,
+}
+
+impl Glide {
+    fn new(dx: i32, dy: i32, duration: Duration) -> Self {
+        let duration_ms = duration.as_millis() as u64;
+        let longest = dx.unsigned_abs().max(dy.unsigned_abs()) as u64;
+        let steps = (duration_ms / GLIDE_STEP_MS)
+            .max(longest.div_ceil(i8::MAX as u64))
+            .max(1);
+        Self {
+            dx: dx as i64,
+            dy: dy as i64,
+            steps: steps as i64,
+            step: 0,
+            step_delay: Duration::from_millis(duration_ms / steps),
+            delay_due: false,
+        }
+    }
+
+    fn position(&self, step: i64) -> (i64, i64) {
+        // Long glides have more steps than fit in an i64 once multiplied by the distance
+        let along = |d: i64| (d as i128 * step as i128 / self.steps as i128) as i64;
+        (along(self.dx), along(self.dy))
+    }
+
+    fn next_action(&mut self) -> Option<HidAction> {
+        if self.step == self.steps {
+            return None;
+        }
+        if self.delay_due {
+            self.delay_due = false;
+            return Some(HidAction::Delay(self.step_delay));
+        }
+        let (from_x, from_y) = self.position(self.step);
+        self.step += 1;
+        let (to_x, to_y) = self.position(self.step);
+        self.delay_due = !self.step_delay.is_zero();
+        Some(HidAction::MouseMove(
+            (to_x - from_x) as i8,
+            (to_y - from_y) as i8,
+        ))
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+enum FrameKind {
+    Once,
+    Repeat { remaining: u32 },
+    WhileHeld { interval: Duration },
+}
+
+#[derive(Debug, Clone)]
+struct Frame {
+    ops: Arc<[MacroOp]>,
+    index: usize,
+    kind: FrameKind,
+    // Actions the cursor had returned when the current iteration started
+    actions_at_start: u64,
+}
+
+/// Walks a macro one HidAction at a time.
+///
+/// `RepeatWhileHeld` loops check whether the button is still held before every
+/// iteration, and a `Delay` of the loop's interval is yielded between iterations,
+/// so the caller always gets a chance to notice a release.
+#[derive(Debug, Clone)]
+pub struct MacroCursor {
+    stack: Vec<Frame>,
+    // Glide in progress, which runs before the rest of the stack
+    glide: Option<Glide>,
+    // Actions returned so far
+    actions: u64,
+}
+
+impl MacroCursor {
+    pub fn new(ops: Vec<MacroOp>) -> Self {
+        Self {
+            stack: vec![Frame {
+                ops: ops.into(),
+                index: 0,
+                kind: FrameKind::Once,
+                actions_at_start: 0,
+            }],
+            glide: None,
+            actions: 0,
+        }
+    }
+
+    /// Returns the next action to run, or None once the macro is done.
+    /// `held` tells whether the button that started the macro is still held down.
+    pub fn next_action(&mut self, held: bool) -> Option<HidAction> {
+        loop {
+            if let Some(glide) = &mut self.glide {
+                match glide.next_action() {
+                    Some(action) => {
+                        self.actions += 1;
+                        return Some(action);
+                    }
+                    None => self.glide = None,
+                }
+            }
+            let frame = self.stack.last_mut()?;
+            if frame.index == 0 && matches!(frame.kind, FrameKind::WhileHeld { .. }) && !held {
+                self.stack.pop();
+                continue;
+            }
+            if let Some(op) = frame.ops.get(frame.index) {
+                frame.index += 1;
+                let frame = match op {
+                    MacroOp::Action(action) => {
+                        self.actions += 1;
+                        return Some(action.clone());
+                    }
+                    MacroOp::Glide { dx, dy, duration } => {
+                        self.glide = Some(Glide::new(*dx, *dy, *duration));
+                        continue;
+                    }
+                    MacroOp::Repeat { count, ops } if *count > 0 && !ops.is_empty() => Frame {
+                        ops: ops.clone(),
+                        index: 0,
+                        kind: FrameKind::Repeat {
+                            remaining: count - 1,
+                        },
+                        actions_at_start: self.actions,
+                    },
+                    MacroOp::RepeatWhileHeld { interval, ops } if held && !ops.is_empty() => {
+                        Frame {
+                            ops: ops.clone(),
+                            index: 0,
+                            kind: FrameKind::WhileHeld {
+                                interval: *interval,
+                            },
+                            actions_at_start: self.actions,
+                        }
+                    }
+                    _ => continue,
+                };
+                self.stack.push(frame);
+                continue;
+            }
+
+            // Reached the end of this frame's ops. An iteration that returned no actions
+            // won't return any the next time either, so there is no point repeating it.
+            match &mut frame.kind {
+                FrameKind::Repeat { remaining }
+                    if *remaining > 0 && self.actions > frame.actions_at_start =>
+                {
+                    *remaining -= 1;
+                    frame.index = 0;
+                    frame.actions_at_start = self.actions;
+                }
+                FrameKind::WhileHeld { interval } if held => {
+                    frame.index = 0;
+                    self.actions += 1;
+                    return Some(HidAction::Delay(*interval));
+                }
+                _ => {
+                    self.stack.pop();
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn action(amount: i8) -> MacroOp {
+        HidAction::MouseWheel(amount).into()
+    }
+
+    fn repeat(count: u32, ops: Vec<MacroOp>) -> MacroOp {
+        MacroOp::Repeat {
+            count,
+            ops: ops.into(),
+        }
+    }
+
+    fn run(cursor: &mut MacroCursor, held: bool) -> Vec<HidAction> {
+        std::iter::from_fn(|| cursor.next_action(held)).collect()
+    }
+
+    #[test]
+    fn runs_actions_in_order() {
+        let mut cursor = MacroCursor::new(vec![action(1), action(2)]);
+        assert_eq!(
+            run(&mut cursor, false),
+            vec![HidAction::MouseWheel(1), HidAction::MouseWheel(2)]
+        );
+        assert_eq!(cursor.next_action(false), None);
+    }
+
+    #[test]
+    fn nested_repeats() {
+        let mut cursor = MacroCursor::new(vec![
+            repeat(2, vec![action(1), repeat(3, vec![action(2)])]),
+            action(3),
+        ]);
+        let actions = run(&mut cursor, false);
+        assert_eq!(actions.len(), 9);
+        assert_eq!(actions[0], HidAction::MouseWheel(1));
+        assert_eq!(actions[4], HidAction::MouseWheel(1));
+        assert_eq!(actions[8], HidAction::MouseWheel(3));
+    }
+
+    #[test]
+    fn repeats_without_actions_are_skipped() {
+        let mut cursor = MacroCursor::new(vec![
+            repeat(0, vec![action(1)]),
+            repeat(u32::MAX, vec![repeat(u32::MAX, Vec::new())]),
+            repeat(
+                u32::MAX,
+                vec![repeat(u32::MAX, vec![repeat(0, vec![action(2)])])],
+            ),
+            action(3),
+        ]);
+        assert_eq!(run(&mut cursor, false), vec![HidAction::MouseWheel(3)]);
+    }
+
+    #[test]
+    fn repeat_while_held_stops_on_release() {
+        let interval = Duration::from_millis(20);
+        let mut cursor = MacroCursor::new(vec![
+            MacroOp::RepeatWhileHeld {
+                interval,
+                ops: vec![action(1)].into(),
+            },
+            action(2),
+        ]);
+        for _ in 0..3 {
+            assert_eq!(cursor.next_action(true), Some(HidAction::MouseWheel(1)));
+            assert_eq!(cursor.next_action(true), Some(HidAction::Delay(interval)));
+        }
+        assert_eq!(cursor.next_action(false), Some(HidAction::MouseWheel(2)));
+        assert_eq!(cursor.next_action(false), None);
+    }
+
+    #[test]
+    fn glide_splits_long_moves() {
+        let mut cursor = MacroCursor::new(vec![MacroOp::Glide {
+            dx: 300,
+            dy: -5,
+            duration: Duration::ZERO,
+        }]);
+        let actions = run(&mut cursor, false);
+        assert_eq!(actions.len(), 3);
+        let (mut x, mut y) = (0, 0);
+        for action in actions {
+            let HidAction::MouseMove(dx, dy) = action else {
+                panic!("unexpected {:?}", action);
+            };
+            (x, y) = (x + dx as i32, y + dy as i32);
+        }
+        assert_eq!((x, y), (300, -5));
+    }
+
+    #[test]
+    fn glide_spreads_steps_over_its_duration() {
+        let delay = HidAction::Delay(Duration::from_millis(10));
+        let mut cursor = MacroCursor::new(vec![
+            MacroOp::Glide {
+                dx: 20,
+                dy: 0,
+                duration: Duration::from_millis(30),
+            },
+            action(1),
+        ]);
+        assert_eq!(
+            run(&mut cursor, false),
+            vec![
+                HidAction::MouseMove(6, 0),
+                delay.clone(),
+                HidAction::MouseMove(7, 0),
+                delay,
+                HidAction::MouseMove(7, 0),
+                HidAction::MouseWheel(1),
+            ]
+        );
+    }
+
+    #[test]
+    fn glide_steps_are_generated_as_the_macro_runs() {
+        let mut cursor = MacroCursor::new(vec![MacroOp::Glide {
+            dx: i32::MAX,
+            dy: i32::MIN,
+            duration: Duration::from_millis(u64::MAX),
+        }]);
+        assert!(matches!(
+            cursor.next_action(false),
+            Some(HidAction::MouseMove(0, 0))
+        ));
+        assert!(matches!(
+            cursor.next_action(false),
+            Some(HidAction::Delay(_))
+        ));
+    }
+
+    #[test]
+    fn repeat_while_held_is_skipped_if_already_released() {
+        let mut cursor = MacroCursor::new(vec![
+            MacroOp::RepeatWhileHeld {
+                interval: Duration::from_millis(20),
+                ops: vec![action(1)].into(),
+            },
+            action(2),
+        ]);
+        assert_eq!(run(&mut cursor, false), vec![HidAction::MouseWheel(2)]);
+    }
+}
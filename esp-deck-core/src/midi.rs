@@ -0,0 +1,122 @@
+/// A MIDI channel voice message. Channels are 0 to 15 here, config actions use 1 to 16.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MidiMessage {
+    NoteOn {
+        channel: u8,
+        note: u8,
+        velocity: u8,
+    },
+    NoteOff {
+        channel: u8,
+        note: u8,
+        velocity: u8,
+    },
+    ControlChange {
+        channel: u8,
+        controller: u8,
+        value: u8,
+    },
+    ProgramChange {
+        channel: u8,
+        program: u8,
+    },
+}
+
+impl MidiMessage {
+    /// Encodes the message as a 4 byte USB-MIDI event packet for a virtual cable.
+    /// Out of range channels and data bytes are masked, so they can't corrupt the stream.
+    pub fn to_usb_packet(self, cable: u8) -> [u8; 4] {
+        let (status, channel, data1, data2) = match self {
+            MidiMessage::NoteOff {
+                channel,
+                note,
+                velocity,
+            } => (0x80, channel, note, velocity),
+            MidiMessage::NoteOn {
+                channel,
+                note,
+                velocity,
+            } => (0x90, channel, note, velocity),
+            MidiMessage::ControlChange {
+                channel,
+                controller,
+                value,
+            } => (0xB0, channel, controller, value),
+            MidiMessage::ProgramChange { channel, program } => (0xC0, channel, program, 0),
+        };
+        // The Code Index Number matches the high nibble of the status for channel messages
+        let code_index = status >> 4;
+        [
+            (cable & 0x0F) << 4 | code_index,
+            status | (channel & 0x0F),
+            data1 & 0x7F,
+            data2 & 0x7F,
+        ]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn note_on() {
+        let message = MidiMessage::NoteOn {
+            channel: 0,
+            note: 60,
+            velocity: 100,
+        };
+        assert_eq!(message.to_usb_packet(0), [0x09, 0x90, 60, 100]);
+    }
+
+    #[test]
+    fn note_off() {
+        let message = MidiMessage::NoteOff {
+            channel: 3,
+            note: 60,
+            velocity: 0,
+        };
+        assert_eq!(message.to_usb_packet(0), [0x08, 0x83, 60, 0]);
+    }
+
+    #[test]
+    fn control_change() {
+        let message = MidiMessage::ControlChange {
+            channel: 15,
+            controller: 7,
+            value: 127,
+        };
+        assert_eq!(message.to_usb_packet(0), [0x0B, 0xBF, 7, 127]);
+    }
+
+    #[test]
+    fn program_change_has_one_data_byte() {
+        let message = MidiMessage::ProgramChange {
+            channel: 9,
+            program: 42,
+        };
+        assert_eq!(message.to_usb_packet(0), [0x0C, 0xC9, 42, 0]);
+    }
+
+    #[test]
+    fn cable_number_goes_in_the_high_nibble() {
+        let message = MidiMessage::NoteOn {
+            channel: 0,
+            note: 1,
+            velocity: 2,
+        };
+        assert_eq!(message.to_usb_packet(5)[0], 0x59);
+        // Only 16 cables fit
+        assert_eq!(message.to_usb_packet(0x15)[0], 0x59);
+    }
+
+    #[test]
+    fn out_of_range_values_are_masked() {
+        let message = MidiMessage::NoteOn {
+            channel: 0x13,
+            note: 0x80,
+            velocity: 0xFF,
+        };
+        assert_eq!(message.to_usb_packet(0), [0x09, 0x93, 0x00, 0x7F]);
+    }
+}
@@ -0,0 +1,117 @@
+use serde::{Deserialize, Serialize};
+
+/// An OSC 1.0 argument. In configs it's a plain JSON value: whole numbers are
+/// sent as int32, other numbers as float32.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(untagged)]
+pub enum OscArg {
+    Bool(bool),
+    Int(i32),
+    Float(f32),
+    String(String),
+}
+
+impl OscArg {
+    fn type_tag(&self) -> char {
+        match self {
+            OscArg::Bool(true) => 'T',
+            OscArg::Bool(false) => 'F',
+            OscArg::Int(_) => 'i',
+            OscArg::Float(_) => 'f',
+            OscArg::String(_) => 's',
+        }
+    }
+}
+
+/// Encodes an OSC message, ready to be sent as a single UDP datagram.
+pub fn encode_message(address: &str, args: &[OscArg]) -> Vec<u8> {
+    let mut packet = Vec::new();
+    push_string(&mut packet, address);
+    let type_tags: String = std::iter::once(',')
+        .chain(args.iter().map(OscArg::type_tag))
+        .collect();
+    push_string(&mut packet, &type_tags);
+    for arg in args {
+        match arg {
+            OscArg::Bool(_) => {} // Carried by the type tag alone
+            OscArg::Int(value) => packet.extend_from_slice(&value.to_be_bytes()),
+            OscArg::Float(value) => packet.extend_from_slice(&value.to_be_bytes()),
+            OscArg::String(value) => push_string(&mut packet, value),
+        }
+    }
+    packet
+}
+
+// OSC strings end with at least one zero byte and are padded to a multiple of 4 bytes
+fn push_string(packet: &mut Vec<u8>, value: &str) {
+    packet.extend_from_slice(value.as_bytes());
+    let padding = 4 - value.len() % 4;
+    packet.resize(packet.len() + padding, 0);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn strings_are_zero_terminated_and_padded() {
+        let mut packet = Vec::new();
+        push_string(&mut packet, "/ab");
+        assert_eq!(packet, b"/ab\0");
+        packet.clear();
+        push_string(&mut packet, "/a");
+        assert_eq!(packet, b"/a\0\0");
+    }
+
+    #[test]
+    fn strings_filling_four_bytes_get_four_zero_bytes() {
+        let mut packet = Vec::new();
+        push_string(&mut packet, "/abc");
+        assert_eq!(packet, b"/abc\0\0\0\0");
+        packet.clear();
+        push_string(&mut packet, "");
+        assert_eq!(packet, b"\0\0\0\0");
+    }
+
+    #[test]
+    fn message_without_args() {
+        assert_eq!(encode_message("/go", &[]), b"/go\0,\0\0\0");
+    }
+
+    #[test]
+    fn bools_only_have_a_type_tag() {
+        let packet = encode_message("/mute", &[OscArg::Bool(true), OscArg::Bool(false)]);
+        assert_eq!(packet, b"/mute\0\0\0,TF\0");
+    }
+
+    #[test]
+    fn int_float_and_string_args() {
+        let packet = encode_message(
+            "/x",
+            &[
+                OscArg::Int(-2),
+                OscArg::Float(1.5),
+                OscArg::String("hi".to_string()),
+            ],
+        );
+        let mut expected = b"/x\0\0,ifs\0\0\0\0".to_vec();
+        expected.extend_from_slice(&[0xFF, 0xFF, 0xFF, 0xFE]);
+        expected.extend_from_slice(&[0x3F, 0xC0, 0x00, 0x00]);
+        expected.extend_from_slice(b"hi\0\0");
+        assert_eq!(packet, expected);
+    }
+
+    #[test]
+    fn config_values_pick_the_arg_type() {
+        let args: Vec<OscArg> = serde_json::from_str(r#"[true, 3, 0.5, "s"]"#).unwrap();
+        assert_eq!(
+            args,
+            vec![
+                OscArg::Bool(true),
+                OscArg::Int(3),
+                OscArg::Float(0.5),
+                OscArg::String("s".to_string()),
+            ]
+        );
+    }
+}
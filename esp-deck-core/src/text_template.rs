@@ -0,0 +1,269 @@
+use chrono::format::{Item, StrftimeItems};
+use chrono::{DateTime, FixedOffset, Utc};
+use std::collections::HashMap;
+use std::net::Ipv4Addr;
+use thiserror::Error;
+
+const DEFAULT_DATE_FORMAT: &str = "%Y-%m-%d";
+const DEFAULT_TIME_FORMAT: &str = "%H:%M:%S";
+// Anything before this means SNTP hasn't set the clock yet
+const CLOCK_SET_AFTER: i64 = 1_577_836_800; // 2020-01-01T00:00:00Z
+
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum TemplateError {
+    #[error("invalid date/time format '{0}'")]
+    InvalidFormat(String),
+    #[error("invalid counter '{0}', use a number")]
+    InvalidCounter(String),
+    #[error("the clock is not set yet")]
+    ClockNotSet,
+    #[error("Wi-Fi is not connected")]
+    NoIpAddress,
+}
+
+enum Placeholder<'a> {
+    Date(&'a str),
+    Time(&'a str),
+    Ip,
+    DeviceName,
+    Counter(u32),
+}
+
+/// Values for the placeholders of typed text:
+///
+/// - `{date}` and `{time}`, in local time. Both take a strftime format, like `{date:%d/%m}`.
+/// - `{ip}`, the address on the Wi-Fi network.
+/// - `{device_name}`.
+/// - `{counter:N}`, counter N, which starts at 1 and goes up each time it's typed
+///   until the device restarts.
+///
+/// Anything else in braces is typed as is.
+#[derive(Debug, Default)]
+pub struct TemplateValues {
+    pub timezone_offset: f32,
+    pub ip: Option<Ipv4Addr>,
+    pub device_name: String,
+    counters: HashMap<u32, u64>,
+}
+
+impl TemplateValues {
+    pub fn expand(&mut self, text: &str, now: DateTime<Utc>) -> Result<String, TemplateError> {
+        let local_time = now.with_timezone(
+            &FixedOffset::east_opt((self.timezone_offset * 3600.0) as i32)
+                .unwrap_or_else(|| FixedOffset::east_opt(0).unwrap()),
+        );
+        expand_with(text, |placeholder| match placeholder {
+            Placeholder::Date(format) | Placeholder::Time(format) => {
+                if now.timestamp() < CLOCK_SET_AFTER {
+                    return Err(TemplateError::ClockNotSet);
+                }
+                Ok(local_time.format(format).to_string())
+            }
+            Placeholder::Ip => self
+                .ip
+                .map(|ip| ip.to_string())
+                .ok_or(TemplateError::NoIpAddress),
+            Placeholder::DeviceName => Ok(self.device_name.clone()),
+            Placeholder::Counter(counter) => {
+                let value = self.counters.entry(counter).or_insert(0);
+                *value += 1;
+                Ok(value.to_string())
+            }
+        })
+    }
+}
+
+/// Checks the placeholders of a text without expanding them, for config validation.
+pub fn check(text: &str) -> Result<(), TemplateError> {
+    expand_with(text, |_| Ok(String::new())).map(|_| ())
+}
+
+/// Returns true if the text has a `{counter:N}` placeholder, which changes each time it's typed.
+pub fn has_counter(text: &str) -> bool {
+    let mut found = false;
+    let _ = expand_with(text, |placeholder| {
+        found |= matches!(placeholder, Placeholder::Counter(_));
+        Ok(String::new())
+    });
+    found
+}
+
+fn expand_with(
+    text: &str,
+    mut value_for: impl FnMut(Placeholder) -> Result<String, TemplateError>,
+) -> Result<String, TemplateError> {
+    let mut expanded = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find('{') {
+        expanded.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let Some(end) = rest.find('}') else {
+            break;
+        };
+        if rest[1..end].contains('{') {
+            // Not the opening brace of the next placeholder
+            expanded.push('{');
+            rest = &rest[1..];
+            continue;
+        }
+        match parse_placeholder(&rest[1..end]) {
+            Some(placeholder) => expanded.push_str(&value_for(placeholder?)?),
+            None => expanded.push_str(&rest[..=end]),
+        }
+        rest = &rest[end + 1..];
+    }
+    expanded.push_str(rest);
+    Ok(expanded)
+}
+
+fn parse_placeholder(name: &str) -> Option<Result<Placeholder<'_>, TemplateError>> {
+    let (name, argument) = match name.split_once(':') {
+        Some((name, argument)) => (name, Some(argument)),
+        None => (name, None),
+    };
+    let placeholder = match (name, argument) {
+        ("date", format) => {
+            check_format(format.unwrap_or(DEFAULT_DATE_FORMAT)).map(Placeholder::Date)
+        }
+        ("time", format) => {
+            check_format(format.unwrap_or(DEFAULT_TIME_FORMAT)).map(Placeholder::Time)
+        }
+        ("ip", None) => Ok(Placeholder::Ip),
+        ("device_name", None) => Ok(Placeholder::DeviceName),
+        ("counter", Some(counter)) => counter
+            .parse()
+            .map(Placeholder::Counter)
+            .map_err(|_| TemplateError::InvalidCounter(counter.to_string())),
+        _ => return None,
+    };
+    Some(placeholder)
+}
+
+// Formatting with an invalid specifier panics, so formats are checked up front
+fn check_format(format: &str) -> Result<&str, TemplateError> {
+    if StrftimeItems::new(format).any(|item| matches!(item, Item::Error)) {
+        Err(TemplateError::InvalidFormat(format.to_string()))
+    } else {
+        Ok(format)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    // 2024-03-05T14:07:09Z
+    fn now() -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2024, 3, 5, 14, 7, 9).unwrap()
+    }
+
+    fn values() -> TemplateValues {
+        TemplateValues {
+            device_name: "deck".to_string(),
+            ip: Some(Ipv4Addr::new(192, 168, 1, 20)),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn expands_each_placeholder() {
+        let mut values = values();
+        assert_eq!(
+            values.expand("{date} {time} {ip} {device_name}", now()),
+            Ok("2024-03-05 14:07:09 192.168.1.20 deck".to_string())
+        );
+        assert_eq!(
+            values.expand("{date:%d/%m} {time:%H%M}", now()),
+            Ok("05/03 1407".to_string())
+        );
+    }
+
+    #[test]
+    fn uses_the_timezone_offset() {
+        let mut values = TemplateValues {
+            timezone_offset: 5.5,
+            ..values()
+        };
+        assert_eq!(values.expand("{time}", now()), Ok("19:37:09".to_string()));
+    }
+
+    #[test]
+    fn counters_go_up_separately() {
+        let mut values = values();
+        assert_eq!(
+            values.expand("{counter:1} {counter:1} {counter:2}", now()),
+            Ok("1 2 1".to_string())
+        );
+        assert_eq!(values.expand("{counter:1}", now()), Ok("3".to_string()));
+    }
+
+    #[test]
+    fn unknown_names_are_typed_as_is() {
+        let mut values = values();
+        let text = "{nope} {ip:x} {counter} {Date} {}";
+        assert_eq!(values.expand(text, now()), Ok(text.to_string()));
+    }
+
+    #[test]
+    fn unclosed_brace_is_typed_as_is() {
+        let mut values = values();
+        assert_eq!(
+            values.expand("{ip} {date", now()),
+            Ok("192.168.1.20 {date".to_string())
+        );
+        assert_eq!(values.expand("{", now()), Ok("{".to_string()));
+    }
+
+    #[test]
+    fn nested_braces_expand_the_inner_placeholder() {
+        let mut values = values();
+        assert_eq!(
+            values.expand("{{ip}}", now()),
+            Ok("{192.168.1.20}".to_string())
+        );
+        assert_eq!(
+            values.expand(r#"{"a": 1} { x {time:%S}}"#, now()),
+            Ok(r#"{"a": 1} { x 09}"#.to_string())
+        );
+    }
+
+    #[test]
+    fn bad_formats_are_errors() {
+        let mut values = values();
+        assert_eq!(
+            values.expand("{date:%Q}", now()),
+            Err(TemplateError::InvalidFormat("%Q".to_string()))
+        );
+        assert_eq!(
+            check("{time:%H:%}"),
+            Err(TemplateError::InvalidFormat("%H:%".to_string()))
+        );
+        assert_eq!(
+            check("{counter:x}"),
+            Err(TemplateError::InvalidCounter("x".to_string()))
+        );
+        assert_eq!(check("{date:%d} {counter:3} {nope}"), Ok(()));
+    }
+
+    #[test]
+    fn missing_values_are_errors() {
+        let mut values = TemplateValues::default();
+        assert_eq!(
+            values.expand("{ip}", now()),
+            Err(TemplateError::NoIpAddress)
+        );
+        assert_eq!(
+            values.expand("{date}", Utc.timestamp_opt(5, 0).unwrap()),
+            Err(TemplateError::ClockNotSet)
+        );
+    }
+
+    #[test]
+    fn finds_counters() {
+        assert!(has_counter("#{counter:1}"));
+        assert!(has_counter("{date} {counter:2}"));
+        assert!(!has_counter("{date} {counter}"));
+        assert!(!has_counter("counter:1"));
+    }
+}
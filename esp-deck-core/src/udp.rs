@@ -0,0 +1,144 @@
+use anyhow::{anyhow, Result};
+use std::net::{Ipv4Addr, SocketAddr, ToSocketAddrs, UdpSocket};
+use std::sync::mpsc::{channel, Sender};
+use std::thread;
+
+/// Sends a single datagram from an ephemeral port. `host` may be a name, an IP
+/// address or a broadcast address.
+pub fn send_to(host: &str, port: u16, payload: &[u8]) -> Result<()> {
+    // The socket is IPv4 only, so a name's IPv6 addresses are skipped
+    let addr = (host, port)
+        .to_socket_addrs()?
+        .find(SocketAddr::is_ipv4)
+        .ok_or_else(|| anyhow!("{} has no IPv4 address", host))?;
+    let socket = UdpSocket::bind((Ipv4Addr::UNSPECIFIED, 0))?;
+    socket.set_broadcast(true)?;
+    let sent = socket.send_to(payload, addr)?;
+    if sent != payload.len() {
+        return Err(anyhow!(
+            "Sent {} of {} bytes to {}:{}",
+            sent,
+            payload.len(),
+            host,
+            port
+        ));
+    }
+    Ok(())
+}
+
+struct Datagram {
+    host: String,
+    port: u16,
+    payload: Vec<u8>,
+    on_error: Box<dyn FnOnce(anyhow::Error) + Send>,
+}
+
+/// Sends datagrams from a thread of its own, so looking up a host name doesn't
+/// hold up the caller.
+pub struct UdpSender {
+    datagram_tx: Sender<Datagram>,
+}
+
+impl UdpSender {
+    pub fn new() -> Self {
+        let (datagram_tx, datagram_rx) = channel::<Datagram>();
+        thread::spawn(move || {
+            while let Ok(datagram) = datagram_rx.recv() {
+                if let Err(e) = send_to(&datagram.host, datagram.port, &datagram.payload) {
+                    (datagram.on_error)(e);
+                }
+            }
+        });
+        Self { datagram_tx }
+    }
+
+    /// Queues a datagram without waiting for it to be sent. `on_error` runs on the
+    /// sender's thread if it can't be.
+    pub fn send(
+        &self,
+        host: String,
+        port: u16,
+        payload: Vec<u8>,
+        on_error: impl FnOnce(anyhow::Error) + Send + 'static,
+    ) -> Result<()> {
+        self.datagram_tx
+            .send(Datagram {
+                host,
+                port,
+                payload,
+                on_error: Box::new(on_error),
+            })
+            .map_err(|_| anyhow!("UDP sender thread is not running"))
+    }
+}
+
+impl Default for UdpSender {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    fn listener() -> (UdpSocket, u16) {
+        let socket = UdpSocket::bind((Ipv4Addr::LOCALHOST, 0)).unwrap();
+        socket
+            .set_read_timeout(Some(Duration::from_secs(5)))
+            .unwrap();
+        let port = socket.local_addr().unwrap().port();
+        (socket, port)
+    }
+
+    #[test]
+    fn send_to_delivers_the_payload() {
+        let (socket, port) = listener();
+        send_to("127.0.0.1", port, b"hello").unwrap();
+        let mut buf = [0; 16];
+        let len = socket.recv(&mut buf).unwrap();
+        assert_eq!(&buf[..len], b"hello");
+    }
+
+    #[test]
+    fn send_to_resolves_host_names() {
+        let (socket, port) = listener();
+        send_to("localhost", port, &[1, 2, 3]).unwrap();
+        let mut buf = [0; 16];
+        let len = socket.recv(&mut buf).unwrap();
+        assert_eq!(&buf[..len], &[1, 2, 3]);
+    }
+
+    #[test]
+    fn send_to_needs_an_ipv4_address() {
+        let e = send_to("::1", 9, b"x").unwrap_err();
+        assert_eq!(e.to_string(), "::1 has no IPv4 address");
+    }
+
+    #[test]
+    fn sender_sends_from_its_thread() {
+        let (socket, port) = listener();
+        let sender = UdpSender::new();
+        sender
+            .send("127.0.0.1".to_string(), port, b"queued".to_vec(), |e| {
+                panic!("send failed: {}", e)
+            })
+            .unwrap();
+        let mut buf = [0; 16];
+        let len = socket.recv(&mut buf).unwrap();
+        assert_eq!(&buf[..len], b"queued");
+    }
+
+    #[test]
+    fn sender_reports_errors() {
+        let sender = UdpSender::new();
+        let (error_tx, error_rx) = channel();
+        sender
+            .send("host.invalid".to_string(), 9, Vec::new(), move |e| {
+                let _ = error_tx.send(e.to_string());
+            })
+            .unwrap();
+        assert!(error_rx.recv_timeout(Duration::from_secs(10)).is_ok());
+    }
+}
@@ -0,0 +1,152 @@
+use crate::hid_action::HidAction;
+use crate::host_os::HostOs;
+
+const CTRL_SHIFT: u8 = 0x03; // Left Ctrl + Left Shift
+const ALT: u8 = 0x04; // Left Alt, which is also Option on macOS
+
+const KEY_SPACE: u8 = 0x2C;
+const KEY_NUMPAD_PLUS: u8 = 0x57;
+const KEY_U: u8 = 0x18;
+
+/// Expands a character into the key sequence that makes the host OS insert it
+/// by its code point, for characters no keyboard layout can produce.
+///
+/// - Linux: Ctrl+Shift+U, the hex code point, then Space (GTK and IBus).
+/// - Windows: hold Alt, Numpad +, then the hex code point. Needs the
+///   `EnableHexNumpad` registry value set on the host.
+/// - macOS: hold Option and type the UTF-16 code units in hex. Needs the
+///   "Unicode Hex Input" input source selected on the host.
+///
+/// Hex digits are typed on the US positions of the letter and number keys.
+pub fn unicode_sequence(c: char, host_os: HostOs) -> Vec<HidAction> {
+    let mut actions = Vec::new();
+    match host_os {
+        HostOs::Linux => {
+            tap(&mut actions, CTRL_SHIFT, KEY_U);
+            for digit in format!("{:x}", c as u32).chars() {
+                tap(&mut actions, 0, hex_digit_key(digit, false));
+            }
+            tap(&mut actions, 0, KEY_SPACE);
+        }
+        HostOs::Windows => {
+            // Alt must stay down until the last digit, releasing it inserts the character
+            tap_held(&mut actions, ALT, KEY_NUMPAD_PLUS);
+            for digit in format!("{:x}", c as u32).chars() {
+                tap_held(&mut actions, ALT, hex_digit_key(digit, true));
+            }
+            actions.push(HidAction::KeyRelease);
+        }
+        HostOs::MacOs => {
+            let mut units = [0u16; 2];
+            for unit in c.encode_utf16(&mut units) {
+                for digit in format!("{:04x}", unit).chars() {
+                    tap_held(&mut actions, ALT, hex_digit_key(digit, false));
+                }
+            }
+            actions.push(HidAction::KeyRelease);
+        }
+    }
+    actions
+}
+
+// Presses and releases a key, along with its modifiers
+fn tap(actions: &mut Vec<HidAction>, modifier: u8, keycode: u8) {
+    actions.push(HidAction::KeyPress(modifier, vec![keycode]));
+    actions.push(HidAction::KeyRelease);
+}
+
+// Presses and releases a key, keeping the modifiers held afterwards
+fn tap_held(actions: &mut Vec<HidAction>, modifier: u8, keycode: u8) {
+    actions.push(HidAction::KeyPress(modifier, vec![keycode]));
+    actions.push(HidAction::KeyPress(modifier, Vec::new()));
+}
+
+// USB HID keycode for a lowercase hex digit, taking digits from the numpad if asked
+fn hex_digit_key(digit: char, numpad: bool) -> u8 {
+    match digit {
+        '0' if numpad => 0x62,
+        '1'..='9' if numpad => 0x59 + (digit as u8 - b'1'),
+        '0' => 0x27,
+        '1'..='9' => 0x1E + (digit as u8 - b'1'),
+        _ => 0x04 + (digit as u8 - b'a'), // a-f
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use HidAction::{KeyPress, KeyRelease};
+
+    #[test]
+    fn linux_types_ctrl_shift_u_then_hex_then_space() {
+        // U+00E9 is typed as "e9"
+        assert_eq!(
+            unicode_sequence('é', HostOs::Linux),
+            vec![
+                KeyPress(CTRL_SHIFT, vec![KEY_U]),
+                KeyRelease,
+                KeyPress(0, vec![0x08]), // e
+                KeyRelease,
+                KeyPress(0, vec![0x26]), // 9
+                KeyRelease,
+                KeyPress(0, vec![KEY_SPACE]),
+                KeyRelease,
+            ]
+        );
+    }
+
+    #[test]
+    fn windows_holds_alt_through_numpad_digits() {
+        // U+20AC is typed as "20ac", digits on the numpad
+        assert_eq!(
+            unicode_sequence('€', HostOs::Windows),
+            vec![
+                KeyPress(ALT, vec![KEY_NUMPAD_PLUS]),
+                KeyPress(ALT, vec![]),
+                KeyPress(ALT, vec![0x5A]), // Numpad 2
+                KeyPress(ALT, vec![]),
+                KeyPress(ALT, vec![0x62]), // Numpad 0
+                KeyPress(ALT, vec![]),
+                KeyPress(ALT, vec![0x04]), // a
+                KeyPress(ALT, vec![]),
+                KeyPress(ALT, vec![0x06]), // c
+                KeyPress(ALT, vec![]),
+                KeyRelease,
+            ]
+        );
+    }
+
+    #[test]
+    fn macos_types_four_digits_per_utf16_unit() {
+        // U+0041 is typed as "0041"
+        assert_eq!(
+            unicode_sequence('A', HostOs::MacOs),
+            vec![
+                KeyPress(ALT, vec![0x27]), // 0
+                KeyPress(ALT, vec![]),
+                KeyPress(ALT, vec![0x27]), // 0
+                KeyPress(ALT, vec![]),
+                KeyPress(ALT, vec![0x21]), // 4
+                KeyPress(ALT, vec![]),
+                KeyPress(ALT, vec![0x1E]), // 1
+                KeyPress(ALT, vec![]),
+                KeyRelease,
+            ]
+        );
+    }
+
+    #[test]
+    fn macos_types_surrogate_pairs() {
+        // U+1F600 is the surrogate pair d83d de00
+        let actions = unicode_sequence('😀', HostOs::MacOs);
+        let digits: Vec<u8> = actions
+            .iter()
+            .filter_map(|action| match action {
+                KeyPress(ALT, keys) => keys.first().copied(),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(digits, vec![0x07, 0x25, 0x20, 0x07, 0x07, 0x08, 0x27, 0x27]);
+        assert_eq!(actions.last(), Some(&KeyRelease));
+    }
+}
@@ -0,0 +1,102 @@
+// The discard port, which is what most tools send the magic packet to
+pub const WAKE_ON_LAN_PORT: u16 = 9;
+pub const DEFAULT_BROADCAST: &str = "255.255.255.255";
+
+/// Parses a MAC address written as 6 hex bytes, separated by ':' or '-' or not at all.
+/// A separated address has to use the same separator between every byte.
+pub fn parse_mac(mac: &str) -> Option<[u8; 6]> {
+    let pairs: Vec<&str> = if mac.len() == 12 {
+        (0..6)
+            .map(|i| mac.get(i * 2..i * 2 + 2))
+            .collect::<Option<_>>()?
+    } else {
+        let separator = if mac.contains(':') { ':' } else { '-' };
+        mac.split(separator).collect()
+    };
+    if pairs.len() != 6 {
+        return None;
+    }
+    let mut bytes = [0; 6];
+    for (byte, pair) in bytes.iter_mut().zip(pairs) {
+        if pair.len() != 2 || !pair.chars().all(|c| c.is_ascii_hexdigit()) {
+            return None;
+        }
+        *byte = u8::from_str_radix(pair, 16).ok()?;
+    }
+    Some(bytes)
+}
+
+/// Builds the magic packet: 6 bytes of 0xFF followed by the MAC address 16 times.
+pub fn magic_packet(mac: [u8; 6]) -> Vec<u8> {
+    let mut packet = vec![0xFF; 6];
+    for _ in 0..16 {
+        packet.extend_from_slice(&mac);
+    }
+    packet
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::udp;
+    use std::net::{Ipv4Addr, UdpSocket};
+    use std::time::Duration;
+
+    const MAC: [u8; 6] = [0x00, 0x11, 0x22, 0xAA, 0xBB, 0xCC];
+
+    #[test]
+    fn parses_separated_and_bare_macs() {
+        assert_eq!(parse_mac("00:11:22:aa:bb:cc"), Some(MAC));
+        assert_eq!(parse_mac("00-11-22-AA-BB-CC"), Some(MAC));
+        assert_eq!(parse_mac("001122AabBcc"), Some(MAC));
+    }
+
+    #[test]
+    fn rejects_wrong_lengths() {
+        assert_eq!(parse_mac(""), None);
+        assert_eq!(parse_mac("00:11:22:aa:bb"), None);
+        assert_eq!(parse_mac("00:11:22:aa:bb:cc:dd"), None);
+        assert_eq!(parse_mac("00112233445"), None);
+        assert_eq!(parse_mac("0:11:22:aa:bb:ccc"), None);
+    }
+
+    #[test]
+    fn rejects_non_hex_digits() {
+        assert_eq!(parse_mac("00:11:22:aa:bb:cg"), None);
+        assert_eq!(parse_mac("00112233445z"), None);
+        assert_eq!(parse_mac("+0:11:22:aa:bb:cc"), None);
+        assert_eq!(parse_mac("0011223344é"), None);
+    }
+
+    #[test]
+    fn rejects_misplaced_or_mixed_separators() {
+        assert_eq!(parse_mac("0011:2233-4455"), None);
+        assert_eq!(parse_mac("::001122334455"), None);
+        assert_eq!(parse_mac("00:11:22-aa:bb:cc"), None);
+        assert_eq!(parse_mac("001:122:aab:bcc"), None);
+        assert_eq!(parse_mac("00:11:22:aa:bb:cc:"), None);
+    }
+
+    #[test]
+    fn magic_packet_repeats_the_mac() {
+        let packet = magic_packet(MAC);
+        assert_eq!(packet.len(), 102);
+        assert_eq!(packet[..6], [0xFF; 6]);
+        for copy in packet[6..].chunks(6) {
+            assert_eq!(copy, MAC);
+        }
+    }
+
+    #[test]
+    fn magic_packet_is_sent_as_one_datagram() {
+        let socket = UdpSocket::bind((Ipv4Addr::LOCALHOST, 0)).unwrap();
+        socket
+            .set_read_timeout(Some(Duration::from_secs(5)))
+            .unwrap();
+        let port = socket.local_addr().unwrap().port();
+        udp::send_to("127.0.0.1", port, &magic_packet(MAC)).unwrap();
+        let mut buf = [0; 256];
+        let len = socket.recv(&mut buf).unwrap();
+        assert_eq!(buf[..len], magic_packet(MAC)[..]);
+    }
+}
@@ -1,134 +1,741 @@
-use crate::bsp::usb_desc::{ConsumerReport, KeyboardReport, MouseReport};
+use crate::bsp::usb_desc::{
+    AbsoluteMouseReport, ConsumerReport, GamepadReport, KeyboardReport, MouseReport,
+    NkroKeyboardReport, RawReport, SystemReport,
+};
+use crate::config::{DeviceSettings, KeyRollover, DEFAULT_TIMEZONE_OFFSET};
 use crate::events::{
-    AppEvent, HidAction,
-    UsbHidCommand::{SendConsumer, SendKeyboard, SendMouse},
+    AppEvent, HidAction, HttpMethod, UrlText, UsbHidCommand,
+    UsbHidCommand::{
+        SendAbsoluteMouse, SendConsumer, SendGamepad, SendKeyboard, SendKeyboardNkro, SendMidi,
+        SendMouse, SendRaw, SendSystem,
+    },
+    WifiStatus,
 };
-use crate::mapper::Mapper;
-use std::sync::mpsc::{Receiver, Sender};
-use std::thread;
+use crate::fetched_text::{self, MAX_RESPONSE_LEN};
+use crate::gesture::{Gesture, GestureDetector};
+use crate::http_client::{HttpClientPool, HttpRequest};
+use crate::keyboard_layout::KeyboardLayout;
+use crate::macros::{MacroCursor, MacroOp, HOLD_REPEAT_INTERVAL};
+use crate::mapper::{typing_sequence, ButtonPhase, Mapper};
+use crate::udp::UdpSender;
+use anyhow::Result;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::net::Ipv4Addr;
+use std::sync::mpsc::{Receiver, RecvTimeoutError, Sender, SyncSender};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+// Most actions a macro runs before the actor checks for new events, so a long
+// macro without delays doesn't hold up button presses and StopAll.
+const MAX_ACTIONS_PER_RUN: usize = 32;
+
+// Only the status of an HttpRequest action is shown, so its response isn't kept in memory
+const MAX_WEBHOOK_RESPONSE_LEN: usize = 1024;
+
+// How long fetched text waits for its button to be pressed again before it's dropped
+const TYPE_CONFIRM_TIMEOUT: Duration = Duration::from_secs(10);
+
+// A macro in progress, along with the ones started on the same button after it.
+// Macros on different buttons run concurrently.
+struct RunningMacro {
+    button_id: i32,
+    cursor: MacroCursor,
+    queued: VecDeque<MacroCursor>,
+    resume_at: Instant,
+}
+
+// Text fetched by TypeFromUrl, typed only once its button is pressed again
+struct PendingText {
+    button_id: i32,
+    ops: Vec<MacroOp>,
+    expires_at: Instant,
+}
 
 pub struct Actor {
     actor_rx: Receiver<AppEvent>,
-    usb_hid_tx: Sender<AppEvent>,
+    // For results of background work, like fetches, to come back to the Actor
+    actor_tx: Sender<AppEvent>,
+    // Bounded, so a macro waits for the USB thread instead of queueing reports without limit
+    usb_hid_tx: SyncSender<AppEvent>,
+    ui_tx: Sender<AppEvent>,
     mapper: Mapper,
+    gestures: GestureDetector,
+    // Page each button was on when pressed, so later phases of the same press
+    // use that page's mapping even if the shown page changed in between
+    pressed_pages: HashMap<i32, usize>,
+    // Buttons that are held down, for RepeatWhileHeld loops
+    held: HashSet<i32>,
+    // Buttons that are held down along with their `while_held` actions
+    hold_sequences: HashMap<i32, Vec<MacroOp>>,
+    next_hold_repeat: Instant,
+    running: Vec<RunningMacro>,
+    key_rollover: KeyRollover,
+    current_mouse_report: MouseReport,
+    current_gamepad_report: GamepadReport,
+    http_pool: Arc<HttpClientPool>,
+    // Address on the Wi-Fi network, None until it's connected
+    wifi_ip: Option<Ipv4Addr>,
+    udp_sender: UdpSender,
+    // Layout of each TypeFromUrl fetch in progress, keyed by button ID
+    fetching: HashMap<i32, Option<String>>,
+    pending_text: Option<PendingText>,
+    // Buttons whose press typed the pending text, so their release does nothing
+    swallow_release: HashSet<i32>,
 }
 
 impl Actor {
-    pub fn new(actor_rx: Receiver<AppEvent>, usb_hid_tx: Sender<AppEvent>, mapper: Mapper) -> Self {
+    pub fn new(
+        actor_rx: Receiver<AppEvent>,
+        actor_tx: Sender<AppEvent>,
+        usb_hid_tx: SyncSender<AppEvent>,
+        ui_tx: Sender<AppEvent>,
+        mut mapper: Mapper,
+        settings: DeviceSettings,
+        http_pool: Arc<HttpClientPool>,
+    ) -> Self {
+        mapper.set_host_os(settings.host_os);
+        mapper.set_timezone_offset(settings.timezone_offset.unwrap_or(DEFAULT_TIMEZONE_OFFSET));
+        mapper.set_device_name(settings.device_name());
         Self {
             actor_rx,
+            actor_tx,
             usb_hid_tx,
+            ui_tx,
             mapper,
+            gestures: GestureDetector::new(settings.gesture_timing()),
+            pressed_pages: HashMap::new(),
+            held: HashSet::new(),
+            hold_sequences: HashMap::new(),
+            next_hold_repeat: Instant::now(),
+            running: Vec::new(),
+            key_rollover: settings.key_rollover.unwrap_or_default(),
+            current_mouse_report: MouseReport::default(),
+            current_gamepad_report: GamepadReport::default(),
+            http_pool,
+            wifi_ip: None,
+            udp_sender: UdpSender::new(),
+            fetching: HashMap::new(),
+            pending_text: None,
+            swallow_release: HashSet::new(),
         }
     }
 
     pub fn run(&mut self) {
         log::info!("Starting Actor");
-        let mut current_mouse_report = MouseReport::default();
 
         loop {
-            match self.actor_rx.recv() {
-                Ok(app_event) => {
-                    if let AppEvent::ButtonPressed(button_id) = app_event {
-                        log::info!("Actor received ButtonPressed: {}", button_id);
-                        let action_sequence = self.mapper.get_action_sequence(button_id);
-                        log::info!("Actor received action sequence: {:?}", action_sequence);
-                        for action in action_sequence {
-                            log::debug!("Actor executing action: {:?}", action);
-                            match action {
-                                HidAction::KeyPress(modifier_bits, keycodes) => {
-                                    let report = KeyboardReport {
-                                        modifier: modifier_bits,
-                                        keys: keycodes,
-                                        ..Default::default()
-                                    };
-                                    log::debug!(
-                                        "Actor sending KeyboardReport: modifier={:#04x}, keys={:?}",
-                                        report.modifier,
-                                        report.keys
-                                    );
-                                    let _ = self
-                                        .usb_hid_tx
-                                        .send(AppEvent::UsbHidCommand(SendKeyboard(report)));
-                                }
-                                HidAction::KeyRelease => {
-                                    let report = KeyboardReport::default();
-                                    log::debug!("Actor sending KeyRelease (empty report)");
-                                    let _ = self
-                                        .usb_hid_tx
-                                        .send(AppEvent::UsbHidCommand(SendKeyboard(report)));
-                                }
-                                HidAction::MouseMove(dx, dy) => {
-                                    let report = MouseReport {
-                                        x: dx,
-                                        y: dy,
-                                        ..Default::default()
-                                    };
-                                    let _ = self
-                                        .usb_hid_tx
-                                        .send(AppEvent::UsbHidCommand(SendMouse(report)));
-                                    let stop_report = MouseReport {
-                                        ..Default::default()
-                                    };
-                                    let _ = self
-                                        .usb_hid_tx
-                                        .send(AppEvent::UsbHidCommand(SendMouse(stop_report)));
-                                }
-                                HidAction::MousePress(buttons) => {
-                                    current_mouse_report.buttons = buttons;
-                                    let _ = self.usb_hid_tx.send(AppEvent::UsbHidCommand(
-                                        SendMouse(current_mouse_report),
-                                    ));
-                                }
-                                HidAction::MouseRelease => {
-                                    current_mouse_report = MouseReport::default();
-                                    let _ = self.usb_hid_tx.send(AppEvent::UsbHidCommand(
-                                        SendMouse(current_mouse_report),
-                                    ));
-                                }
-                                HidAction::MouseWheel(amount) => {
-                                    let report = MouseReport {
-                                        wheel: amount,
-                                        ..Default::default()
-                                    };
-                                    let _ = self
-                                        .usb_hid_tx
-                                        .send(AppEvent::UsbHidCommand(SendMouse(report)));
-                                    let stop_report = MouseReport {
-                                        ..Default::default()
-                                    };
-                                    let _ = self
-                                        .usb_hid_tx
-                                        .send(AppEvent::UsbHidCommand(SendMouse(stop_report)));
-                                }
-                                HidAction::ConsumerPress(usage_id) => {
-                                    let report = ConsumerReport { usage: usage_id };
-                                    let _ = self
-                                        .usb_hid_tx
-                                        .send(AppEvent::UsbHidCommand(SendConsumer(report)));
-                                }
-                                HidAction::ConsumerRelease => {
-                                    let report = ConsumerReport { usage: 0 };
-                                    let _ = self
-                                        .usb_hid_tx
-                                        .send(AppEvent::UsbHidCommand(SendConsumer(report)));
-                                }
-                                HidAction::Delay(duration) => {
-                                    thread::sleep(duration);
-                                }
+            self.run_macros();
+            let deadline = [
+                (!self.hold_sequences.is_empty()).then_some(self.next_hold_repeat),
+                self.gestures.next_deadline(),
+                self.running.iter().map(|m| m.resume_at).min(),
+                self.pending_text.as_ref().map(|pending| pending.expires_at),
+            ]
+            .into_iter()
+            .flatten()
+            .min();
+            let received = match deadline {
+                Some(deadline) => self
+                    .actor_rx
+                    .recv_timeout(deadline.saturating_duration_since(Instant::now())),
+                None => self
+                    .actor_rx
+                    .recv()
+                    .map_err(|_| RecvTimeoutError::Disconnected),
+            };
+
+            match received {
+                Ok(AppEvent::ButtonPressed(button_id)) => {
+                    log::info!("Actor received ButtonPressed: {}", button_id);
+                    self.held.insert(button_id);
+                    if let Some(pending) = self.pending_text.take() {
+                        if pending.button_id == button_id && Instant::now() < pending.expires_at {
+                            self.start_macro(button_id, pending.ops);
+                            self.swallow_release.insert(button_id);
+                            continue;
+                        }
+                        // Pressing anything else counts as a no
+                        let _ = self
+                            .ui_tx
+                            .send(AppEvent::StatusMessage("Fetched text dropped".to_string()));
+                    }
+                    let page = self.mapper.current_page();
+                    self.pressed_pages.insert(button_id, page);
+                    let bindings = self.mapper.get_gesture_bindings(page, button_id);
+                    if bindings.any() {
+                        self.gestures.press(button_id, bindings, Instant::now());
+                        continue;
+                    }
+                    let action_sequence = self.sequence_for(button_id, ButtonPhase::Press);
+                    log::info!("Actor received action sequence: {:?}", action_sequence);
+                    self.start_macro(button_id, action_sequence);
+
+                    let hold_sequence = self.sequence_for(button_id, ButtonPhase::Hold);
+                    if !hold_sequence.is_empty() {
+                        if self.hold_sequences.is_empty() {
+                            self.next_hold_repeat = Instant::now();
+                        }
+                        self.hold_sequences.insert(button_id, hold_sequence);
+                    }
+                }
+                Ok(AppEvent::ButtonReleased(button_id)) => {
+                    log::info!("Actor received ButtonReleased: {}", button_id);
+                    self.held.remove(&button_id);
+                    if self.swallow_release.remove(&button_id) {
+                        continue;
+                    }
+                    if self.gestures.is_tracking(button_id) {
+                        match self.gestures.release(button_id, Instant::now()) {
+                            Some(gesture) => self.dispatch_gesture(button_id, gesture),
+                            None if !self.gestures.is_tracking(button_id) => {
+                                self.pressed_pages.remove(&button_id);
                             }
+                            None => {}
                         }
-                    } else if let AppEvent::MappingUpdated(mapping_config) = app_event {
-                        self.mapper.update_mapping_config(mapping_config);
+                        continue;
+                    }
+                    self.hold_sequences.remove(&button_id);
+                    let action_sequence = self.sequence_for(button_id, ButtonPhase::Release);
+                    self.pressed_pages.remove(&button_id);
+                    self.start_macro(button_id, action_sequence);
+                }
+                Ok(AppEvent::PageSelected(page)) => {
+                    log::info!("Actor received PageSelected: {}", page);
+                    if self.mapper.switch_page(page) {
+                        self.notify_page_changed();
+                    }
+                }
+                Ok(AppEvent::MappingUpdated(pages)) => {
+                    let page = self.mapper.current_page();
+                    // The UI shows the page and button names
+                    let _ = self.ui_tx.send(AppEvent::MappingUpdated(pages.clone()));
+                    let unlatched = self.mapper.update_mapping_config(pages);
+                    if self.mapper.current_page() != page {
+                        self.notify_page_changed();
                     } else {
-                        log::warn!("Actor received unexpected event: {:?}", app_event);
+                        for button_id in unlatched {
+                            let _ = self.ui_tx.send(AppEvent::ButtonLatched(button_id, false));
+                        }
+                    }
+                }
+                Ok(AppEvent::SettingsUpdated(settings)) => {
+                    self.gestures.set_timing(settings.gesture_timing());
+                    self.mapper.set_host_os(settings.host_os);
+                    self.mapper.set_timezone_offset(
+                        settings.timezone_offset.unwrap_or(DEFAULT_TIMEZONE_OFFSET),
+                    );
+                    self.mapper.set_device_name(settings.device_name());
+                    let key_rollover = settings.key_rollover.unwrap_or_default();
+                    if key_rollover != self.key_rollover {
+                        // Keys held through the old report would otherwise stay down
+                        if let Err(e) = self.send_action(HidAction::KeyRelease) {
+                            log::error!("Failed to release keys: {}", e);
+                        }
+                        self.key_rollover = key_rollover;
+                    }
+                }
+                Ok(AppEvent::KeyboardLeds(leds)) => {
+                    log::info!("Actor received KeyboardLeds: {:?}", leds);
+                    self.mapper.set_keyboard_leds(leds);
+                    let _ = self.ui_tx.send(AppEvent::KeyboardLeds(leds));
+                }
+                Ok(AppEvent::WifiUpdate(status)) => {
+                    self.wifi_ip = match status {
+                        WifiStatus::Connected(ip) => Some(ip),
+                        _ => None,
+                    };
+                    self.mapper.set_ip_address(self.wifi_ip);
+                }
+                Ok(AppEvent::TextFetched(button_id, text)) => {
+                    self.text_fetched(button_id, text);
+                }
+                Ok(app_event) => {
+                    log::warn!("Actor received unexpected event: {:?}", app_event);
+                }
+                Err(RecvTimeoutError::Timeout) => {
+                    let now = Instant::now();
+                    if self
+                        .pending_text
+                        .as_ref()
+                        .is_some_and(|pending| now >= pending.expires_at)
+                    {
+                        self.pending_text = None;
+                        let _ = self
+                            .ui_tx
+                            .send(AppEvent::StatusMessage("Fetched text expired".to_string()));
+                    }
+                    for (button_id, gesture) in self.gestures.poll(now) {
+                        self.dispatch_gesture(button_id, gesture);
+                    }
+                    if !self.hold_sequences.is_empty() && now >= self.next_hold_repeat {
+                        // Buttons still busy with an earlier run skip this round instead
+                        // of piling up runs
+                        let hold_sequences: Vec<(i32, Vec<MacroOp>)> = self
+                            .hold_sequences
+                            .iter()
+                            .filter(|(button_id, _)| {
+                                !self.running.iter().any(|m| m.button_id == **button_id)
+                            })
+                            .map(|(button_id, ops)| (*button_id, ops.clone()))
+                            .collect();
+                        for (button_id, hold_sequence) in hold_sequences {
+                            self.start_macro(button_id, hold_sequence);
+                        }
+                        self.next_hold_repeat = Instant::now() + HOLD_REPEAT_INTERVAL;
                     }
                 }
-                Err(e) => {
-                    log::error!("Actor failed to receive event: {}. Exiting.", e);
+                Err(RecvTimeoutError::Disconnected) => {
+                    log::error!("Actor failed to receive event: channel disconnected. Exiting.");
                     break;
                 }
             }
         }
     }
+
+    /// Translates the actions for a button phase on the page the button was pressed on,
+    /// letting the UI know if a Toggle, page navigation or SetHostOs action changed what it shows.
+    fn sequence_for(&mut self, button_id: i32, phase: ButtonPhase) -> Vec<MacroOp> {
+        let current_page = self.mapper.current_page();
+        let page = self
+            .pressed_pages
+            .get(&button_id)
+            .copied()
+            .unwrap_or(current_page);
+        let was_latched = self.mapper.is_latched(page, button_id);
+        let host_os = self.mapper.host_os();
+        let action_sequence = self.mapper.get_action_sequence(page, button_id, phase);
+        let latched = self.mapper.is_latched(page, button_id);
+        if self.mapper.current_page() != current_page {
+            self.notify_page_changed();
+        } else if page == current_page && latched != was_latched {
+            let _ = self.ui_tx.send(AppEvent::ButtonLatched(button_id, latched));
+        }
+        if let Some(new_host_os) = self.mapper.host_os().filter(|os| Some(*os) != host_os) {
+            let _ = self.ui_tx.send(AppEvent::StatusMessage(format!(
+                "Host OS: {:?} (until restart)",
+                new_host_os
+            )));
+        }
+        action_sequence
+    }
+
+    /// Tells the UI to show the current page, along with the latched buttons on it.
+    fn notify_page_changed(&self) {
+        let page = self.mapper.current_page();
+        let latched_buttons = self.mapper.latched_buttons(page);
+        let _ = self
+            .ui_tx
+            .send(AppEvent::PageChanged(page, latched_buttons));
+    }
+
+    fn dispatch_gesture(&mut self, button_id: i32, gesture: Gesture) {
+        log::info!("Actor recognised {:?} on button {}", gesture, button_id);
+        let phases: &[ButtonPhase] = match gesture {
+            Gesture::Tap => &[ButtonPhase::Press, ButtonPhase::Release],
+            Gesture::LongPress => &[ButtonPhase::LongPress],
+            Gesture::DoubleTap => &[ButtonPhase::DoubleTap],
+        };
+        for phase in phases {
+            let action_sequence = self.sequence_for(button_id, *phase);
+            self.start_macro(button_id, action_sequence);
+        }
+        if !self.gestures.is_tracking(button_id) {
+            self.pressed_pages.remove(&button_id);
+        }
+    }
+
+    /// Starts a macro for a button. If the button already has one running, the new
+    /// one runs after it, so the phases of a press happen in order.
+    fn start_macro(&mut self, button_id: i32, ops: Vec<MacroOp>) {
+        if ops.is_empty() {
+            return;
+        }
+        let cursor = MacroCursor::new(ops);
+        match self.running.iter_mut().find(|m| m.button_id == button_id) {
+            Some(running) => running.queued.push_back(cursor),
+            None => self.running.push(RunningMacro {
+                button_id,
+                cursor,
+                queued: VecDeque::new(),
+                resume_at: Instant::now(),
+            }),
+        }
+    }
+
+    /// Advances every macro that is due until it reaches a delay or finishes.
+    fn run_macros(&mut self) {
+        let now = Instant::now();
+        let mut index = 0;
+        let mut actions_run = 0;
+        while index < self.running.len() {
+            if self.running[index].resume_at > now || actions_run == MAX_ACTIONS_PER_RUN {
+                // A macro that used up its actions carries on once pending events are handled
+                index += 1;
+                actions_run = 0;
+                continue;
+            }
+            actions_run += 1;
+            let button_id = self.running[index].button_id;
+            let held = self.held.contains(&button_id);
+            match self.running[index].cursor.next_action(held) {
+                Some(HidAction::Delay(duration)) => {
+                    self.running[index].resume_at = Instant::now() + duration;
+                    index += 1;
+                    actions_run = 0;
+                }
+                Some(HidAction::TypeFromUrl(request)) => {
+                    self.fetch_text(button_id, request);
+                }
+                Some(HidAction::StopMacros(target)) => {
+                    self.stop_macros(target, button_id);
+                    // The list may have shrunk. If this macro was stopped too, the ones
+                    // after it run on the next round.
+                    let position = self.running.iter().position(|m| m.button_id == button_id);
+                    if position != Some(index) {
+                        index = position.unwrap_or(self.running.len());
+                        actions_run = 0;
+                    }
+                }
+                Some(action) => {
+                    if let Err(e) = self.send_action(action) {
+                        log::error!("Macro on button {} failed: {}", button_id, e);
+                        self.running.remove(index);
+                        actions_run = 0;
+                        self.release_all();
+                    }
+                }
+                None => {
+                    let running = &mut self.running[index];
+                    match running.queued.pop_front() {
+                        Some(cursor) => running.cursor = cursor,
+                        None => {
+                            self.running.remove(index);
+                            actions_run = 0;
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    /// Starts fetching the text for a TypeFromUrl action. The result comes back
+    /// as a TextFetched event.
+    fn fetch_text(&mut self, button_id: i32, request: UrlText) {
+        let error = if self.wifi_ip.is_none() {
+            Some("Wi-Fi is not connected".to_string())
+        } else if self.fetching.contains_key(&button_id) {
+            Some("still fetching".to_string())
+        } else {
+            let actor_tx = self.actor_tx.clone();
+            let json_pointer = request.json_pointer;
+            let submitted = self.http_pool.submit(HttpRequest {
+                method: HttpMethod::Get.into(),
+                url: request.url,
+                headers: Vec::new(),
+                body: None,
+                max_body_len: Some(MAX_RESPONSE_LEN),
+                on_response: Box::new(move |result| {
+                    let text = match result {
+                        Ok(response) if (200..300).contains(&response.status) => {
+                            fetched_text::text_to_type(&response.body, json_pointer.as_deref())
+                                .map_err(|e| e.to_string())
+                        }
+                        Ok(response) => Err(format!("HTTP status {}", response.status)),
+                        Err(e) => Err(e.to_string()),
+                    };
+                    let _ = actor_tx.send(AppEvent::TextFetched(button_id, text));
+                }),
+            });
+            match submitted {
+                Ok(()) => {
+                    self.fetching.insert(button_id, request.layout);
+                    None
+                }
+                Err(e) => Some(e.to_string()),
+            }
+        };
+        if let Some(e) = error {
+            log::error!("TypeFromUrl on button {} failed: {}", button_id, e);
+            let _ = self.ui_tx.send(AppEvent::StatusMessage(format!(
+                "TypeFromUrl failed: {}",
+                e
+            )));
+        }
+    }
+
+    /// Holds on to fetched text until its button is pressed again, so nothing is
+    /// typed into whatever has focus by the time a slow fetch finishes.
+    fn text_fetched(&mut self, button_id: i32, text: Result<String, String>) {
+        let Some(layout) = self.fetching.remove(&button_id) else {
+            return;
+        };
+        let key_strokes = text.and_then(|text| {
+            KeyboardLayout::from_name(layout.as_deref())
+                .and_then(|layout| layout.translate(&text))
+                .map_err(|e| e.to_string())
+        });
+        let message = match key_strokes {
+            Ok(key_strokes) if key_strokes.is_empty() => "Fetched text is empty".to_string(),
+            Ok(key_strokes) => {
+                self.pending_text = Some(PendingText {
+                    button_id,
+                    ops: typing_sequence(key_strokes),
+                    expires_at: Instant::now() + TYPE_CONFIRM_TIMEOUT,
+                });
+                format!(
+                    "Press button {} again within {} s to type the fetched text",
+                    button_id,
+                    TYPE_CONFIRM_TIMEOUT.as_secs()
+                )
+            }
+            Err(e) => {
+                log::error!("TypeFromUrl on button {} failed: {}", button_id, e);
+                format!("TypeFromUrl failed: {}", e)
+            }
+        };
+        let _ = self.ui_tx.send(AppEvent::StatusMessage(message));
+    }
+
+    /// Cancels the macros of the given button, or of every button other than `caller`
+    /// if none is given, and releases everything they may have left pressed.
+    fn stop_macros(&mut self, target: Option<i32>, caller: i32) {
+        log::info!(
+            "Stopping macros of {}",
+            target.map_or("all other buttons".to_string(), |id| format!(
+                "button {}",
+                id
+            ))
+        );
+        let stopped = |button_id: i32| match target {
+            Some(target) => button_id == target,
+            None => button_id != caller,
+        };
+        let running_before = self.running.len();
+        self.running.retain(|m| !stopped(m.button_id));
+        self.hold_sequences
+            .retain(|button_id, _| !stopped(*button_id));
+        if self.running.len() != running_before {
+            self.release_all();
+        }
+    }
+
+    /// Releases all keys, mouse buttons and consumer controls, so nothing is left
+    /// stuck down on the host when a macro is cut short.
+    fn release_all(&mut self) {
+        for action in [
+            HidAction::KeyRelease,
+            HidAction::MouseRelease,
+            HidAction::ConsumerRelease,
+            HidAction::SystemRelease,
+            HidAction::GamepadRelease(None),
+        ] {
+            if let Err(e) = self.send_action(action) {
+                log::error!("Failed to release everything: {}", e);
+            }
+        }
+    }
+
+    fn send_hid(&self, command: UsbHidCommand) -> Result<()> {
+        self.usb_hid_tx
+            .send(AppEvent::UsbHidCommand(command))
+            .map_err(|e| anyhow::anyhow!("Failed to send HID command: {}", e))
+    }
+
+    fn send_action(&mut self, action: HidAction) -> Result<()> {
+        log::debug!("Actor executing action: {:?}", action);
+        match action {
+            HidAction::KeyPress(modifier_bits, keycodes) => match self.key_rollover {
+                KeyRollover::SixKey => {
+                    if keycodes.len() > 6 {
+                        log::warn!(
+                            "6KRO report can only hold 6 keys, dropping {:?}. Switch to NKRO for larger chords",
+                            &keycodes[6..]
+                        );
+                    }
+                    let mut report = KeyboardReport {
+                        modifier: modifier_bits,
+                        ..Default::default()
+                    };
+                    for (slot, keycode) in report.keys.iter_mut().zip(keycodes) {
+                        *slot = keycode;
+                    }
+                    log::debug!(
+                        "Actor sending KeyboardReport: modifier={:#04x}, keys={:?}",
+                        report.modifier,
+                        report.keys
+                    );
+                    self.send_hid(SendKeyboard(report))
+                }
+                KeyRollover::NKey => {
+                    let mut report = NkroKeyboardReport {
+                        modifier: modifier_bits,
+                        ..Default::default()
+                    };
+                    for keycode in keycodes {
+                        report.press(keycode);
+                    }
+                    log::debug!(
+                        "Actor sending NkroKeyboardReport: modifier={:#04x}, keys={:?}",
+                        report.modifier,
+                        report.keys
+                    );
+                    self.send_hid(SendKeyboardNkro(report))
+                }
+            },
+            HidAction::KeyRelease => {
+                log::debug!("Actor sending KeyRelease (empty report)");
+                match self.key_rollover {
+                    KeyRollover::SixKey => self.send_hid(SendKeyboard(KeyboardReport::default())),
+                    KeyRollover::NKey => {
+                        self.send_hid(SendKeyboardNkro(NkroKeyboardReport::default()))
+                    }
+                }
+            }
+            HidAction::MouseMove(dx, dy) => {
+                // Held buttons stay held, so that moves in between press and release drag
+                let report = MouseReport {
+                    x: dx,
+                    y: dy,
+                    ..self.current_mouse_report
+                };
+                self.send_hid(SendMouse(report))?;
+                self.send_hid(SendMouse(self.current_mouse_report))
+            }
+            HidAction::MouseMoveTo(x, y) => {
+                // Positions are absolute, so unlike relative moves there is nothing to reset
+                let report = AbsoluteMouseReport { buttons: 0, x, y };
+                self.send_hid(SendAbsoluteMouse(report))
+            }
+            HidAction::MousePress(buttons) => {
+                self.current_mouse_report.buttons = buttons;
+                self.send_hid(SendMouse(self.current_mouse_report))
+            }
+            HidAction::MouseRelease => {
+                self.current_mouse_report = MouseReport::default();
+                self.send_hid(SendMouse(self.current_mouse_report))
+            }
+            HidAction::MouseWheel(amount) => {
+                let report = MouseReport {
+                    wheel: amount,
+                    ..self.current_mouse_report
+                };
+                self.send_hid(SendMouse(report))?;
+                self.send_hid(SendMouse(self.current_mouse_report))
+            }
+            HidAction::MouseHScroll(amount) => {
+                let report = MouseReport {
+                    pan: amount,
+                    ..self.current_mouse_report
+                };
+                self.send_hid(SendMouse(report))?;
+                self.send_hid(SendMouse(self.current_mouse_report))
+            }
+            HidAction::ConsumerPress(usage_id) => {
+                let report = ConsumerReport { usage: usage_id };
+                self.send_hid(SendConsumer(report))
+            }
+            HidAction::ConsumerRelease => {
+                let report = ConsumerReport { usage: 0 };
+                self.send_hid(SendConsumer(report))
+            }
+            HidAction::SystemPress(control) => {
+                let report = SystemReport { control };
+                self.send_hid(SendSystem(report))
+            }
+            HidAction::SystemRelease => {
+                let report = SystemReport { control: 0 };
+                self.send_hid(SendSystem(report))
+            }
+            HidAction::GamepadPress(button) => {
+                self.current_gamepad_report.buttons |= 1 << (button - 1);
+                self.send_hid(SendGamepad(self.current_gamepad_report))
+            }
+            HidAction::GamepadRelease(Some(button)) => {
+                self.current_gamepad_report.buttons &= !(1 << (button - 1));
+                self.send_hid(SendGamepad(self.current_gamepad_report))
+            }
+            HidAction::GamepadRelease(None) => {
+                self.current_gamepad_report = GamepadReport::default();
+                self.send_hid(SendGamepad(self.current_gamepad_report))
+            }
+            HidAction::GamepadAxis(axis, value) => {
+                match axis {
+                    0 => self.current_gamepad_report.x = value,
+                    _ => self.current_gamepad_report.y = value,
+                }
+                self.send_hid(SendGamepad(self.current_gamepad_report))
+            }
+            HidAction::GamepadHat(direction) => {
+                self.current_gamepad_report.hat = direction;
+                self.send_hid(SendGamepad(self.current_gamepad_report))
+            }
+            HidAction::Midi(message) => self.send_hid(SendMidi(message.to_usb_packet(0))),
+            HidAction::RawReport(bytes) => {
+                let mut report = RawReport::default();
+                let len = bytes.len().min(report.data.len());
+                report.data[..len].copy_from_slice(&bytes[..len]);
+                self.send_hid(SendRaw(report))
+            }
+            HidAction::HttpRequest(request) => {
+                // Network actions are best effort, a failure is shown but doesn't stop the macro.
+                // Runs on the pool's thread so a slow server doesn't hold up other buttons.
+                let summary = format!("HTTP {:?} {}", request.method, request.url);
+                let ui_tx = self.ui_tx.clone();
+                let report = move |message: String| {
+                    log::info!("{}", message);
+                    let _ = ui_tx.send(AppEvent::StatusMessage(message));
+                };
+                let submitted = match self.wifi_ip {
+                    Some(_) => {
+                        let summary = summary.clone();
+                        let report = report.clone();
+                        self.http_pool.submit(HttpRequest {
+                            method: request.method.into(),
+                            url: request.url,
+                            headers: request.headers,
+                            body: request.body.map(String::into_bytes),
+                            max_body_len: Some(MAX_WEBHOOK_RESPONSE_LEN),
+                            on_response: Box::new(move |result| {
+                                report(match result {
+                                    Ok(response) => format!("{}: {}", summary, response.status),
+                                    Err(e) => format!("{} failed: {}", summary, e),
+                                })
+                            }),
+                        })
+                    }
+                    None => Err(anyhow::anyhow!("Wi-Fi is not connected")),
+                };
+                if let Err(e) = submitted {
+                    report(format!("{} failed: {}", summary, e));
+                }
+                Ok(())
+            }
+            HidAction::Udp(host, port, payload) => {
+                // Network actions are best effort, a failure is shown but doesn't stop the macro.
+                // Sent from the UDP sender's thread, since looking up the host can take a while.
+                let summary = format!("UDP {}:{}", host, port);
+                let ui_tx = self.ui_tx.clone();
+                let report = move |e: anyhow::Error| {
+                    log::error!("{} failed: {}", summary, e);
+                    let _ = ui_tx.send(AppEvent::StatusMessage(format!(
+                        "{} failed: {}",
+                        summary, e
+                    )));
+                };
+                match self.wifi_ip {
+                    Some(_) => {
+                        if let Err(e) = self.udp_sender.send(host, port, payload, report.clone()) {
+                            report(e);
+                        }
+                    }
+                    None => report(anyhow::anyhow!("Wi-Fi is not connected")),
+                }
+                Ok(())
+            }
+            HidAction::Delay(_) | HidAction::StopMacros(_) | HidAction::TypeFromUrl(_) => {
+                // Handled by the macro scheduler in `run_macros`
+                Ok(())
+            }
+        }
+    }
 }
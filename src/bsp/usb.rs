@@ -1,20 +1,24 @@
 use anyhow::Result;
 use esp_idf_svc::sys::{
-    hid_report_type_t, tinyusb_config_t, tinyusb_config_t__bindgen_ty_1,
-    tinyusb_config_t__bindgen_ty_2, tinyusb_config_t__bindgen_ty_2__bindgen_ty_1,
-    tinyusb_driver_install, tud_control_status, tud_vendor_n_read_flush, tud_vendor_n_write,
-    tud_vendor_n_write_flush,
+    hid_report_type_t, hid_report_type_t_HID_REPORT_TYPE_OUTPUT, tinyusb_config_t,
+    tinyusb_config_t__bindgen_ty_1, tinyusb_config_t__bindgen_ty_2,
+    tinyusb_config_t__bindgen_ty_2__bindgen_ty_1, tinyusb_driver_install, tud_control_status,
+    tud_vendor_n_read_flush, tud_vendor_n_write, tud_vendor_n_write_flush,
+};
+use esp_idf_svc::sys::{
+    tud_control_xfer, tud_hid_n_report, tud_midi_n_packet_write, tusb_control_request_t,
 };
-use esp_idf_svc::sys::{tud_control_xfer, tud_hid_n_report, tusb_control_request_t};
 use std::collections::VecDeque;
 use std::ptr;
 use std::sync::{mpsc::Sender, LazyLock, Mutex, OnceLock};
 use thiserror::Error;
 
 use crate::bsp::usb_desc::{
-    TUSB_DESC_BOS, TUSB_DESC_CONFIGURATION, TUSB_DESC_DEVICE, TUSB_DESC_HID_REPORT,
+    KeyboardLeds, REPORT_ID_KEYBOARD, REPORT_ID_RAW, TUSB_DESC_BOS, TUSB_DESC_CONFIGURATION,
+    TUSB_DESC_DEVICE, TUSB_DESC_HID_REPORT,
 };
 use crate::events::{AppEvent, UsbStatus};
+use crate::raw_hid::parse_output_report;
 
 const MAGIC_WORD: u32 = 0xE59DECC0;
 // Magic Word + Payload length bytes
@@ -48,6 +52,8 @@ enum UsbRxState {
 
 static USB_UPDATE_TX: OnceLock<Mutex<Sender<AppEvent>>> = OnceLock::new();
 static PROCESS_MESSAGE_TX: OnceLock<Mutex<Sender<Vec<u8>>>> = OnceLock::new();
+// Receives what the host sends through HID output reports
+static HID_OUTPUT_TX: OnceLock<Mutex<Sender<AppEvent>>> = OnceLock::new();
 static USB_RX_BUFFER: LazyLock<Mutex<UsbRxBuffer>> =
     LazyLock::new(|| Mutex::new(UsbRxBuffer::default()));
 
@@ -96,6 +102,34 @@ extern "C" fn tud_hid_set_report_cb(
         report_type,
         buffsize
     );
+    if report_type != hid_report_type_t_HID_REPORT_TYPE_OUTPUT || buffer.is_null() {
+        return;
+    }
+    let report = unsafe { std::slice::from_raw_parts(buffer, buffsize as usize) };
+    // The HID interface has no OUT endpoint, so output reports come in as SET_REPORT
+    // requests on the control endpoint. TinyUSB takes the report ID off those and passes
+    // it as `report_id`. Only reports from an OUT endpoint come with ID 0 and the ID
+    // still leading the data.
+    let (report_id, report) = match report_id {
+        0 => match report.split_first() {
+            Some((report_id, report)) => (*report_id, report),
+            None => return,
+        },
+        report_id => (report_id, report),
+    };
+    match report_id {
+        REPORT_ID_KEYBOARD => {
+            if let Some(leds) = report.first() {
+                send_event(&HID_OUTPUT_TX, AppEvent::KeyboardLeds(KeyboardLeds(*leds)));
+            }
+        }
+        REPORT_ID_RAW => {
+            if let Some(event) = parse_output_report(report) {
+                send_event(&USB_UPDATE_TX, event);
+            }
+        }
+        _ => log::warn!("Ignoring output report with ID {}", report_id),
+    }
 }
 
 fn send_usb_update(status: UsbStatus) {
@@ -115,6 +149,18 @@ fn send_usb_update(status: UsbStatus) {
     usb_update_tx.send(AppEvent::UsbUpdate(status)).unwrap();
 }
 
+fn send_event(tx: &OnceLock<Mutex<Sender<AppEvent>>>, event: AppEvent) {
+    match tx.get().map(|tx| tx.lock()) {
+        Some(Ok(tx)) => {
+            if let Err(e) = tx.send(event) {
+                log::error!("Failed to send event from USB: {}", e);
+            }
+        }
+        Some(Err(e)) => log::error!("Failed to lock event sender: {}", e),
+        None => log::error!("Event sender is not initialized"),
+    }
+}
+
 fn process_message(message: Vec<u8>) -> bool {
     let usb_message_tx = match PROCESS_MESSAGE_TX.get() {
         Some(tx) => match tx.lock() {
@@ -481,7 +527,11 @@ pub struct Usb;
 impl Usb {
     #[allow(unused_unsafe)]
     #[allow(static_mut_refs)]
-    pub fn new(usb_update_tx: Sender<AppEvent>, message_tx: Sender<Vec<u8>>) -> Self {
+    pub fn new(
+        usb_update_tx: Sender<AppEvent>,
+        message_tx: Sender<Vec<u8>>,
+        hid_output_tx: Sender<AppEvent>,
+    ) -> Self {
         let tusb_config = tinyusb_config_t {
             string_descriptor: unsafe { crate::bsp::usb_desc::STRING_DESCRIPTOR.as_mut_ptr() },
             string_descriptor_count: crate::bsp::usb_desc::STRING_DESCRIPTOR_LEN as i32,
@@ -518,6 +568,13 @@ impl Usb {
             }
         }
 
+        match HID_OUTPUT_TX.set(Mutex::new(hid_output_tx)) {
+            Ok(_) => (),
+            Err(e) => {
+                log::error!("Failed to set HID_OUTPUT_TX: {:?}", e);
+            }
+        }
+
         send_usb_update(UsbStatus::Initialized);
 
         Self {}
@@ -529,4 +586,8 @@ impl Usb {
             tud_hid_n_report(itf, report_id, data_ptr, report_len as u16)
         }
     }
+
+    pub fn send_midi_packet(packet: &[u8; 4]) -> bool {
+        unsafe { tud_midi_n_packet_write(0, packet.as_ptr()) }
+    }
 }
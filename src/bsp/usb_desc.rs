@@ -10,6 +10,27 @@ pub struct KeyboardReport {
     pub keys: [u8; 6],
 }
 
+// Keyboard usages 0x00-0xDF as a bitmap, the modifiers 0xE0-0xE7 have their own byte
+pub const NKRO_KEY_BYTES: usize = 28;
+
+/// Keyboard report with one bit per key, so any number of keys can be down at once.
+#[repr(C)]
+#[derive(Default, Debug, Clone, Copy)]
+pub struct NkroKeyboardReport {
+    pub modifier: u8,
+    pub keys: [u8; NKRO_KEY_BYTES],
+}
+
+impl NkroKeyboardReport {
+    /// Marks a key as pressed. Keycodes outside the bitmap are ignored.
+    pub fn press(&mut self, keycode: u8) {
+        let idx = keycode as usize / 8;
+        if idx < NKRO_KEY_BYTES {
+            self.keys[idx] |= 1 << (keycode % 8);
+        }
+    }
+}
+
 #[repr(C)]
 #[derive(Default, Debug, Clone, Copy)]
 pub struct MouseReport {
@@ -17,6 +38,32 @@ pub struct MouseReport {
     pub x: i8,       // Movement in X direction (-127 to 127)
     pub y: i8,       // Movement in Y direction (-127 to 127)
     pub wheel: i8,   // Wheel movement (-127 to 127)
+    pub pan: i8,     // Horizontal scroll movement (-127 to 127)
+}
+
+// LED output report the host sends to the keyboard
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KeyboardLeds(pub u8); // Bit 0: Num Lock, 1: Caps Lock, 2: Scroll Lock, 3: Compose, 4: Kana
+
+impl KeyboardLeds {
+    pub fn is_lit(self, bit: u8) -> bool {
+        self.0 & (1 << bit) != 0
+    }
+
+    pub fn caps_lock(self) -> bool {
+        self.is_lit(1)
+    }
+}
+
+// Largest coordinate of the absolute pointer, covering the whole screen
+pub const ABSOLUTE_MOUSE_MAX: u16 = 32767;
+
+#[repr(C, packed)]
+#[derive(Default, Debug, Clone, Copy)]
+pub struct AbsoluteMouseReport {
+    pub buttons: u8, // Always 0, clicks go through MouseReport
+    pub x: u16,      // 0 (left edge) to ABSOLUTE_MOUSE_MAX (right edge)
+    pub y: u16,      // 0 (top edge) to ABSOLUTE_MOUSE_MAX (bottom edge)
 }
 
 #[repr(C)]
@@ -25,13 +72,58 @@ pub struct ConsumerReport {
     pub usage: u16, // Consumer Usage ID
 }
 
+#[repr(C)]
+#[derive(Default, Debug, Clone, Copy)]
+pub struct SystemReport {
+    pub control: u8, // 0: None, 1: Power Down, 2: Sleep, 3: Wake Up
+}
+
+// Number of buttons in the gamepad report
+pub const GAMEPAD_BUTTONS: u8 = 32;
+// Hat switch value for no direction, directions are 0 (up) to 7 (up left) going clockwise
+pub const GAMEPAD_HAT_CENTERED: u8 = 8;
+
+#[repr(C, packed)]
+#[derive(Debug, Clone, Copy)]
+pub struct GamepadReport {
+    pub buttons: u32, // Bit 0: Button 1 ... Bit 31: Button 32
+    pub x: i8,        // X axis (-127 to 127)
+    pub y: i8,        // Y axis (-127 to 127)
+    pub hat: u8,      // Low 4 bits: hat direction or GAMEPAD_HAT_CENTERED
+}
+
+impl Default for GamepadReport {
+    fn default() -> Self {
+        Self {
+            buttons: 0,
+            x: 0,
+            y: 0,
+            hat: GAMEPAD_HAT_CENTERED,
+        }
+    }
+}
+
+// Size of the vendor defined raw report, in both directions
+pub const RAW_REPORT_LEN: usize = 32;
+
+#[repr(C)]
+#[derive(Default, Debug, Clone, Copy)]
+pub struct RawReport {
+    pub data: [u8; RAW_REPORT_LEN], // Free form, zero padded
+}
+
 // Report IDs
 pub const REPORT_ID_KEYBOARD: u8 = 1;
 pub const REPORT_ID_MOUSE: u8 = 2;
 pub const REPORT_ID_CONSUMER: u8 = 3;
+pub const REPORT_ID_KEYBOARD_NKRO: u8 = 4;
+pub const REPORT_ID_ABSOLUTE_MOUSE: u8 = 5;
+pub const REPORT_ID_SYSTEM: u8 = 6;
+pub const REPORT_ID_GAMEPAD: u8 = 7;
+pub const REPORT_ID_RAW: u8 = 8;
 
 // Update this if you change TUSB_DESC_HID_REPORT
-const REPORT_DESCRIPTOR_LEN: u16 = 137;
+const REPORT_DESCRIPTOR_LEN: u16 = 379;
 
 // HID Report Descriptor
 pub const TUSB_DESC_HID_REPORT: [u8; REPORT_DESCRIPTOR_LEN as usize] = [
@@ -60,16 +152,16 @@ pub const TUSB_DESC_HID_REPORT: [u8; REPORT_DESCRIPTOR_LEN as usize] = [
     0x19, 0x00, //   Usage Minimum (Reserved)
     0x29, 0xFF, //   Usage Maximum (possibly Keyboard Lang.) ; Adjust range as needed
     0x81, 0x00, //   Input (Data, Array) ; Keycode array (6 bytes)
-    // Optional: Keyboard Output Report for LEDs (Num lock, Caps lock etc)
-    // 0x95, 0x05,       //   Report Count (5) ; Num, Caps, Scroll, Compose, Kana
-    // 0x75, 0x01,       //   Report Size (1)
-    // 0x05, 0x08,       //   Usage Page (LEDs)
-    // 0x19, 0x01,       //   Usage Minimum (Num Lock)
-    // 0x29, 0x05,       //   Usage Maximum (Kana)
-    // 0x91, 0x02,       //   Output (Data, Variable, Absolute) ; LED Report
-    // 0x95, 0x01,       //   Report Count (1)
-    // 0x75, 0x03,       //   Report Size (3)
-    // 0x91, 0x03,       //   Output (Constant) ; LED Report Padding
+    // Keyboard Output Report for LEDs (Num lock, Caps lock etc)
+    0x95, 0x05, //   Report Count (5) ; Num, Caps, Scroll, Compose, Kana
+    0x75, 0x01, //   Report Size (1)
+    0x05, 0x08, //   Usage Page (LEDs)
+    0x19, 0x01, //   Usage Minimum (Num Lock)
+    0x29, 0x05, //   Usage Maximum (Kana)
+    0x91, 0x02, //   Output (Data, Variable, Absolute) ; LED Report
+    0x95, 0x01, //   Report Count (1)
+    0x75, 0x03, //   Report Size (3)
+    0x91, 0x03, //   Output (Constant) ; LED Report Padding
     0xC0, // End Collection (Keyboard)
     // --- Mouse TLC ---
     0x05, 0x01, // Usage Page (Generic Desktop)
@@ -106,6 +198,14 @@ pub const TUSB_DESC_HID_REPORT: [u8; REPORT_DESCRIPTOR_LEN as usize] = [
     0x75, 0x08, //     Report Size (8)
     0x95, 0x01, //     Report Count (1) ; Wheel
     0x81, 0x06, //     Input (Data, Variable, Relative) ; Wheel movement
+    // Horizontal scroll
+    0x05, 0x0C, //     Usage Page (Consumer Devices)
+    0x0A, 0x38, 0x02, //     Usage (AC Pan)
+    0x15, 0x81, //     Logical Minimum (-127)
+    0x25, 0x7F, //     Logical Maximum (127)
+    0x75, 0x08, //     Report Size (8)
+    0x95, 0x01, //     Report Count (1) ; Pan
+    0x81, 0x06, //     Input (Data, Variable, Relative) ; Horizontal scroll movement
     0xC0, //   End Collection (Physical)
     0xC0, // End Collection (Mouse)
     // --- Consumer Control TLC ---
@@ -123,10 +223,129 @@ pub const TUSB_DESC_HID_REPORT: [u8; REPORT_DESCRIPTOR_LEN as usize] = [
     0x95, 0x01, //   Report Count (1) - Send one Usage ID per report
     0x81, 0x00, //   Input (Data, Array) ; The Consumer Usage ID
     0xC0, // End Collection (Consumer Control)
+    // --- NKRO Keyboard TLC ---
+    0x05, 0x01, // Usage Page (Generic Desktop)
+    0x09, 0x06, // Usage (Keyboard)
+    0xA1, 0x01, // Collection (Application)
+    0x85, 0x04, //   Report ID (4) <-- NKRO KEYBOARD ID = 4
+    // Modifier Byte, same as the 6KRO keyboard
+    0x05, 0x07, //   Usage Page (Key Codes)
+    0x19, 0xE0, //   Usage Minimum (Left Ctrl)
+    0x29, 0xE7, //   Usage Maximum (Right GUI)
+    0x15, 0x00, //   Logical Minimum (0)
+    0x25, 0x01, //   Logical Maximum (1)
+    0x75, 0x01, //   Report Size (1) bit
+    0x95, 0x08, //   Report Count (8) bits
+    0x81, 0x02, //   Input (Data, Variable, Absolute) ; Modifier Byte
+    // One bit per key
+    0x05, 0x07, //   Usage Page (Key Codes)
+    0x19, 0x00, //   Usage Minimum (Reserved)
+    0x29, 0xDF, //   Usage Maximum (0xDF)
+    0x15, 0x00, //   Logical Minimum (0)
+    0x25, 0x01, //   Logical Maximum (1)
+    0x75, 0x01, //   Report Size (1) bit
+    0x96, 0xE0, 0x00, //   Report Count (224) bits
+    0x81, 0x02, //   Input (Data, Variable, Absolute) ; Key bitmap (28 bytes)
+    0xC0, // End Collection (NKRO Keyboard)
+    // --- Absolute Pointer TLC ---
+    0x05, 0x01, // Usage Page (Generic Desktop)
+    0x09, 0x02, // Usage (Mouse)
+    0xA1, 0x01, // Collection (Application)
+    0x85, 0x05, //   Report ID (5) <-- ABSOLUTE MOUSE ID = 5
+    0x09, 0x01, //   Usage (Pointer)
+    0xA1, 0x00, //   Collection (Physical)
+    // Buttons, some hosts don't accept a pointer without them
+    0x05, 0x09, //     Usage Page (Button)
+    0x19, 0x01, //     Usage Minimum (Button 1)
+    0x29, 0x03, //     Usage Maximum (Button 3)
+    0x15, 0x00, //     Logical Minimum (0)
+    0x25, 0x01, //     Logical Maximum (1)
+    0x75, 0x01, //     Report Size (1) bit
+    0x95, 0x03, //     Report Count (3) buttons
+    0x81, 0x02, //     Input (Data, Variable, Absolute) ; Button states
+    0x95, 0x01, //     Report Count (1)
+    0x75, 0x05, //     Report Size (5) bits
+    0x81, 0x03, //     Input (Constant) ; Padding to fill byte
+    // Pointer X, Y Position (Absolute)
+    0x05, 0x01, //     Usage Page (Generic Desktop)
+    0x09, 0x30, //     Usage (X)
+    0x09, 0x31, //     Usage (Y)
+    0x15, 0x00, //     Logical Minimum (0)
+    0x26, 0xFF, 0x7F, //     Logical Maximum (32767)
+    0x75, 0x10, //     Report Size (16) bits
+    0x95, 0x02, //     Report Count (2) ; X, Y
+    0x81, 0x02, //     Input (Data, Variable, Absolute) ; X, Y position
+    0xC0, //   End Collection (Physical)
+    0xC0, // End Collection (Absolute Pointer)
+    // --- System Control TLC ---
+    0x05, 0x01, // Usage Page (Generic Desktop)
+    0x09, 0x80, // Usage (System Control)
+    0xA1, 0x01, // Collection (Application)
+    0x85, 0x06, //   Report ID (6) <-- SYSTEM CONTROL ID = 6
+    0x19, 0x81, //   Usage Minimum (System Power Down)
+    0x29, 0x83, //   Usage Maximum (System Wake Up)
+    0x15, 0x01, //   Logical Minimum (1)
+    0x25, 0x03, //   Logical Maximum (3)
+    0x75, 0x08, //   Report Size (8) bits
+    0x95, 0x01, //   Report Count (1) - One control at a time
+    0x81, 0x00, //   Input (Data, Array) ; Index of the control, 0 for none
+    0xC0, // End Collection (System Control)
+    // --- Gamepad TLC ---
+    0x05, 0x01, // Usage Page (Generic Desktop)
+    0x09, 0x05, // Usage (Game Pad)
+    0xA1, 0x01, // Collection (Application)
+    0x85, 0x07, //   Report ID (7) <-- GAMEPAD ID = 7
+    // Buttons
+    0x05, 0x09, //   Usage Page (Button)
+    0x19, 0x01, //   Usage Minimum (Button 1)
+    0x29, 0x20, //   Usage Maximum (Button 32)
+    0x15, 0x00, //   Logical Minimum (0)
+    0x25, 0x01, //   Logical Maximum (1)
+    0x75, 0x01, //   Report Size (1) bit
+    0x95, 0x20, //   Report Count (32) buttons
+    0x81, 0x02, //   Input (Data, Variable, Absolute) ; Button states
+    // X, Y Axes
+    0x05, 0x01, //   Usage Page (Generic Desktop)
+    0x09, 0x30, //   Usage (X)
+    0x09, 0x31, //   Usage (Y)
+    0x15, 0x81, //   Logical Minimum (-127)
+    0x25, 0x7F, //   Logical Maximum (127)
+    0x75, 0x08, //   Report Size (8) bits
+    0x95, 0x02, //   Report Count (2) ; X, Y
+    0x81, 0x02, //   Input (Data, Variable, Absolute) ; Axis positions
+    // Hat Switch
+    0x09, 0x39, //   Usage (Hat switch)
+    0x15, 0x00, //   Logical Minimum (0)
+    0x25, 0x07, //   Logical Maximum (7)
+    0x35, 0x00, //   Physical Minimum (0)
+    0x46, 0x3B, 0x01, //   Physical Maximum (315)
+    0x65, 0x14, //   Unit (Degrees)
+    0x75, 0x04, //   Report Size (4) bits
+    0x95, 0x01, //   Report Count (1)
+    0x81,
+    0x42, //   Input (Data, Variable, Absolute, Null State) ; Direction, out of range is centered
+    0x65, 0x00, //   Unit (None)
+    0x81, 0x03, //   Input (Constant) ; 4 bits padding to fill byte
+    0xC0, // End Collection (Gamepad)
+    // --- Raw Vendor TLC, for host applications talking to the deck through hidapi ---
+    0x06, 0x00, 0xFF, // Usage Page (Vendor Defined 0xFF00)
+    0x09, 0x01, // Usage (Vendor Usage 1)
+    0xA1, 0x01, // Collection (Application)
+    0x85, 0x08, //   Report ID (8) <-- RAW ID = 8
+    0x15, 0x00, //   Logical Minimum (0)
+    0x26, 0xFF, 0x00, //   Logical Maximum (255)
+    0x75, 0x08, //   Report Size (8) bits
+    0x95, 0x20, //   Report Count (32) bytes
+    0x09, 0x02, //   Usage (Vendor Usage 2)
+    0x81, 0x02, //   Input (Data, Variable, Absolute) ; Deck to host
+    0x09, 0x03, //   Usage (Vendor Usage 3)
+    0x91, 0x02, //   Output (Data, Variable, Absolute) ; Host to deck
+    0xC0, // End Collection (Raw Vendor)
 ];
 
 // !!! MUST BE >= LARGEST REPORT DATA SIZE + 1 (for Report ID prefix) !!!
-// Keyboard=8 bytes data -> min 9. Mouse=4 -> min 5. Consumer=2 -> min 3.
+// Keyboard=8 bytes data -> min 9. Mouse=5 -> min 6. Consumer=2 -> min 3. NKRO Keyboard=29 -> min 30.
+// Absolute Mouse=5 -> min 6. System=1 -> min 2. Gamepad=7 -> min 8. Raw=32 -> min 33.
 // But we also need to account for bulk endpoints, so let's use 64 which is common for bulk endpoints
 const MAX_PACKET_SIZE: u16 = 64;
 // Polling interval for the HID Interrupt IN endpoint (in milliseconds)
@@ -143,10 +362,16 @@ const USB_MAX_POWER_MA: u8 = 100; // Max power in mA
 
 // --- Calculated Total Configuration Descriptor Size ---
 // Config (9) + Interface (9) + HID (9) + Endpoint (7) + Vendor (9) + Vendor Endpoint  Out (7)  + Vendor Endpoint In (7) = 57 bytes
-const CONFIG_DESC_TOTAL_LEN: u16 = 57;
+// + Audio Control Interface (9) + AC Header (9) + MIDI Streaming Interface (9) + MS Class Descriptors (65) = 149 bytes
+const CONFIG_DESC_TOTAL_LEN: u16 = 149;
+// Class specific MIDI Streaming descriptors: MS Header (7) + 2 IN Jacks (6 + 6) + 2 OUT Jacks (9 + 9)
+// + 2 Endpoints (9 + 9) + 2 Class Specific Endpoints (5 + 5) = 65 bytes
+const MIDI_MS_DESC_TOTAL_LEN: u16 = 65;
 
 // WebUSB and MS OS 2.0 constants
 pub const ITF_NUM_VENDOR: u8 = 1;
+pub const ITF_NUM_AUDIO_CONTROL: u8 = 2;
+pub const ITF_NUM_MIDI_STREAMING: u8 = 3;
 pub const VENDOR_REQUEST_WEBUSB: u8 = 1;
 pub const VENDOR_REQUEST_MICROSOFT: u8 = 2;
 
@@ -158,6 +383,8 @@ pub const TURB_REQUEST_TYPE_INVALID: u8 = 3;
 // Endpoint assignments. 0 is control, HID uses 1 IN
 const EP_VENDOR_OUT: u8 = 0x02;
 const EP_VENDOR_IN: u8 = 0x82;
+const EP_MIDI_OUT: u8 = 0x03;
+const EP_MIDI_IN: u8 = 0x83;
 
 #[rustfmt::skip]
 pub const TUSB_DESC_CONFIGURATION: [u8; CONFIG_DESC_TOTAL_LEN as usize] = [
@@ -166,7 +393,7 @@ pub const TUSB_DESC_CONFIGURATION: [u8; CONFIG_DESC_TOTAL_LEN as usize] = [
     usb_constants::descriptor_type::CONFIGURATION, // bDescriptorType: CONFIGURATION (0x02)
     (CONFIG_DESC_TOTAL_LEN & 0xFF) as u8,       // wTotalLength (Low Byte): Total length (Config + Interface + HID + Endpoint)
     (CONFIG_DESC_TOTAL_LEN >> 8) as u8,         // wTotalLength (High Byte)
-    0x02,                                       // bNumInterfaces: 2 interfaces (HID + Vendor)
+    0x04,                                       // bNumInterfaces: 4 interfaces (HID + Vendor + Audio Control + MIDI Streaming)
     0x01,                                       // bConfigurationValue: Configuration value 1
     CONFIG_STRING_INDEX,                        // iConfiguration: Index of string descriptor (0 = None)
     USB_CONFIG_ATTR,                            // bmAttributes: (e.g., 0xA0 = Bus powered + Remote Wakeup)
@@ -232,7 +459,79 @@ pub const TUSB_DESC_CONFIGURATION: [u8; CONFIG_DESC_TOTAL_LEN as usize] = [
     (MAX_PACKET_SIZE & 0xFF) as u8,             // wMaxPacketSize (Low Byte): Max packet size (e.g., 16 bytes)
     (MAX_PACKET_SIZE >> 8) as u8,               // wMaxPacketSize (High Byte)
     0x00,                                       // bInterval: Ignored for Bulk endpoints
-    
+
+    // Audio Control Interface: Interface 2, required by the MIDI Streaming interface
+    // --- Interface Descriptor (9 bytes) ---
+    0x09,                                       // bLength: Size of this descriptor (9 bytes)
+    usb_constants::descriptor_type::INTERFACE,  // bDescriptorType: INTERFACE (0x04)
+    ITF_NUM_AUDIO_CONTROL,                      // bInterfaceNumber: Interface Number 2
+    0x00,                                       // bAlternateSetting: Alternate Setting 0
+    0x00,                                       // bNumEndpoints: No endpoints
+    usb_constants::class_code::AUDIO,           // bInterfaceClass: Audio (0x01)
+    usb_constants::audio_subclass::CONTROL,     // bInterfaceSubClass: Audio Control (0x01)
+    0x00,                                       // bInterfaceProtocol: None
+    0x00,                                       // iInterface: Index of string descriptor (0 = None)
+
+    // --- Class Specific AC Header (9 bytes) ---
+    0x09,                                       // bLength: Size of this descriptor (9 bytes)
+    usb_constants::descriptor_type::CS_INTERFACE, // bDescriptorType: CS_INTERFACE (0x24)
+    0x01,                                       // bDescriptorSubtype: HEADER
+    0x00, 0x01,                                 // bcdADC: Audio Device Class release 1.00 LSB, MSB
+    0x09, 0x00,                                 // wTotalLength: Just this header
+    0x01,                                       // bInCollection: 1 streaming interface
+    ITF_NUM_MIDI_STREAMING,                     // baInterfaceNr: The MIDI Streaming interface
+
+    // MIDI Streaming Interface: Interface 3
+    // --- Interface Descriptor (9 bytes) ---
+    0x09,                                       // bLength: Size of this descriptor (9 bytes)
+    usb_constants::descriptor_type::INTERFACE,  // bDescriptorType: INTERFACE (0x04)
+    ITF_NUM_MIDI_STREAMING,                     // bInterfaceNumber: Interface Number 3
+    0x00,                                       // bAlternateSetting: Alternate Setting 0
+    0x02,                                       // bNumEndpoints: 2 endpoints for this interface (Out/In)
+    usb_constants::class_code::AUDIO,           // bInterfaceClass: Audio (0x01)
+    usb_constants::audio_subclass::MIDI_STREAMING, // bInterfaceSubClass: MIDI Streaming (0x03)
+    0x00,                                       // bInterfaceProtocol: None
+    0x00,                                       // iInterface: Index of string descriptor (0 = None)
+
+    // --- Class Specific MS Header (7 bytes) ---
+    0x07,                                       // bLength: Size of this descriptor (7 bytes)
+    usb_constants::descriptor_type::CS_INTERFACE, // bDescriptorType: CS_INTERFACE (0x24)
+    0x01,                                       // bDescriptorSubtype: MS_HEADER
+    0x00, 0x01,                                 // bcdMSC: MIDI Streaming release 1.00 LSB, MSB
+    (MIDI_MS_DESC_TOTAL_LEN & 0xFF) as u8,      // wTotalLength (Low Byte): All class specific MS descriptors
+    (MIDI_MS_DESC_TOTAL_LEN >> 8) as u8,        // wTotalLength (High Byte)
+
+    // --- MIDI IN Jacks (6 bytes each) ---
+    0x06, usb_constants::descriptor_type::CS_INTERFACE, 0x02, 0x01, 0x01, 0x00, // Embedded IN Jack 1, fed by the host
+    0x06, usb_constants::descriptor_type::CS_INTERFACE, 0x02, 0x02, 0x02, 0x00, // External IN Jack 2
+
+    // --- MIDI OUT Jacks (9 bytes each) ---
+    0x09, usb_constants::descriptor_type::CS_INTERFACE, 0x03, 0x01, 0x03, 0x01, 0x02, 0x01, 0x00, // Embedded OUT Jack 3, to the host, sourced from Jack 2
+    0x09, usb_constants::descriptor_type::CS_INTERFACE, 0x03, 0x02, 0x04, 0x01, 0x01, 0x01, 0x00, // External OUT Jack 4, sourced from Jack 1
+
+    // --- Endpoint Descriptor Out (9 bytes, audio endpoints carry 2 extra bytes) ---
+    0x09,                                       // bLength: Size of this descriptor (9 bytes)
+    usb_constants::descriptor_type::ENDPOINT,   // bDescriptorType: ENDPOINT (0x05)
+    EP_MIDI_OUT,                                // bEndpointAddress: Endpoint 3, OUT direction (MSB=0 for OUT)
+    usb_constants::endpoint_attribute::BULK,    // bmAttributes: Bulk transfer type (0x02)
+    (MAX_PACKET_SIZE & 0xFF) as u8,             // wMaxPacketSize (Low Byte)
+    (MAX_PACKET_SIZE >> 8) as u8,               // wMaxPacketSize (High Byte)
+    0x00,                                       // bInterval: Ignored for Bulk endpoints
+    0x00, 0x00,                                 // bRefresh, bSynchAddress: Unused
+    // --- Class Specific Endpoint Out (5 bytes) ---
+    0x05, usb_constants::descriptor_type::CS_ENDPOINT, 0x01, 0x01, 0x01, // MS_GENERAL, 1 jack: Embedded IN Jack 1
+
+    // --- Endpoint Descriptor In (9 bytes) ---
+    0x09,                                       // bLength: Size of this descriptor (9 bytes)
+    usb_constants::descriptor_type::ENDPOINT,   // bDescriptorType: ENDPOINT (0x05)
+    EP_MIDI_IN,                                 // bEndpointAddress: Endpoint 3, IN direction (MSB=1 for IN)
+    usb_constants::endpoint_attribute::BULK,    // bmAttributes: Bulk transfer type (0x02)
+    (MAX_PACKET_SIZE & 0xFF) as u8,             // wMaxPacketSize (Low Byte)
+    (MAX_PACKET_SIZE >> 8) as u8,               // wMaxPacketSize (High Byte)
+    0x00,                                       // bInterval: Ignored for Bulk endpoints
+    0x00, 0x00,                                 // bRefresh, bSynchAddress: Unused
+    // --- Class Specific Endpoint In (5 bytes) ---
+    0x05, usb_constants::descriptor_type::CS_ENDPOINT, 0x01, 0x01, 0x03, // MS_GENERAL, 1 jack: Embedded OUT Jack 3
 ];
 
 // Device Descriptor
@@ -321,11 +620,18 @@ pub mod usb_constants {
         pub const PHYSICAL: u8 = 0x23;
         pub const BOS: u8 = 0x0F;
         pub const DEVICE_CAPABILITY: u8 = 0x10;
+        pub const CS_INTERFACE: u8 = 0x24;
+        pub const CS_ENDPOINT: u8 = 0x25;
     }
     pub mod class_code {
+        pub const AUDIO: u8 = 0x01;
         pub const HID: u8 = 0x03;
         pub const VENDOR_SPEC: u8 = 0xFF;
     }
+    pub mod audio_subclass {
+        pub const CONTROL: u8 = 0x01;
+        pub const MIDI_STREAMING: u8 = 0x03;
+    }
     pub mod endpoint_attribute {
         pub const CONTROL: u8 = 0x00;
         pub const ISOCHRONOUS: u8 = 0x01;
@@ -339,8 +645,13 @@ pub mod usb_constants {
 
 // Check struct sizes at compile time (not necessary, but good practice)
 const _: () = assert!(std::mem::size_of::<KeyboardReport>() == 8);
-const _: () = assert!(std::mem::size_of::<MouseReport>() == 4);
+const _: () = assert!(std::mem::size_of::<NkroKeyboardReport>() == 29);
+const _: () = assert!(std::mem::size_of::<MouseReport>() == 5);
+const _: () = assert!(std::mem::size_of::<AbsoluteMouseReport>() == 5);
 const _: () = assert!(std::mem::size_of::<ConsumerReport>() == 2);
+const _: () = assert!(std::mem::size_of::<SystemReport>() == 1);
+const _: () = assert!(std::mem::size_of::<GamepadReport>() == 7);
+const _: () = assert!(std::mem::size_of::<RawReport>() == RAW_REPORT_LEN);
 
 // --- Add BOS Descriptor ---
 // Correct BOS Calculation: BOS Header (5) + WebUSB Cap (24) + MS OS Cap (28) = 57
@@ -17,6 +17,7 @@ use std::sync::mpsc::Sender;
 pub struct Wifi {
     wifi_driver: AsyncWifi<EspWifi<'static>>,
     tx: Sender<AppEvent>,
+    actor_tx: Sender<AppEvent>,
     wifi_settings: Option<WifiSettings>,
 }
 
@@ -27,19 +28,29 @@ impl Wifi {
         nvs: EspDefaultNvsPartition,
         timer_service: EspTaskTimerService,
         tx: Sender<AppEvent>,
+        actor_tx: Sender<AppEvent>,
     ) -> Result<Self> {
         let wifi_driver = AsyncWifi::wrap(
             EspWifi::new(modem, sys_loop.clone(), Some(nvs))?,
             sys_loop,
             timer_service,
         )?;
-        tx.send(AppEvent::WifiUpdate(WifiStatus::Initializing))?;
-
-        Ok(Self {
+        let wifi = Self {
             wifi_driver,
             tx,
+            actor_tx,
             wifi_settings: None,
-        })
+        };
+        wifi.send_status(WifiStatus::Initializing)?;
+
+        Ok(wifi)
+    }
+
+    // The UI shows the status, and the Actor needs it to know when network actions can run
+    fn send_status(&self, status: WifiStatus) -> Result<()> {
+        let _ = self.actor_tx.send(AppEvent::WifiUpdate(status.clone()));
+        self.tx.send(AppEvent::WifiUpdate(status))?;
+        Ok(())
     }
 
     pub async fn connect(&mut self, wifi_settings: Option<WifiSettings>) -> Result<()> {
@@ -48,7 +59,7 @@ impl Wifi {
             .ok_or_else(|| anyhow!("No Wi-Fi credentials provided"))?;
         self.wifi_settings = Some(settings.clone());
 
-        self.tx.send(AppEvent::WifiUpdate(WifiStatus::Connecting))?;
+        self.send_status(WifiStatus::Connecting)?;
         let wifi_config: Configuration = Configuration::Client(ClientConfiguration {
             ssid: settings
                 .ssid
@@ -75,13 +86,12 @@ impl Wifi {
 
         match self.wifi_driver.wait_netif_up().await {
             Ok(_) => {
-                self.tx.send(AppEvent::WifiUpdate(WifiStatus::Connected(
+                self.send_status(WifiStatus::Connected(
                     self.wifi_driver.wifi().sta_netif().get_ip_info()?.ip,
-                )))?;
+                ))?;
             }
             Err(e) => {
-                self.tx
-                    .send(AppEvent::WifiUpdate(WifiStatus::Error(e.to_string())))?;
+                self.send_status(WifiStatus::Error(e.to_string()))?;
             }
         }
         info!("WiFi interface is up");
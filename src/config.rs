@@ -1,4 +1,5 @@
-use crate::mapper::MappingConfiguration;
+use crate::gesture::GestureTiming;
+use crate::mapper::{Mapper, MappingConfiguration, MappingError, MappingErrorKind};
 use anyhow::Result;
 use serde::de::{self, Deserializer};
 use serde::{Deserialize, Serialize};
@@ -8,6 +9,9 @@ use std::{
     io::{Read, Write},
     sync::{Arc, Mutex},
 };
+use thiserror::Error;
+
+pub use esp_deck_core::host_os::HostOs;
 
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub struct WifiSettings {
@@ -15,12 +19,48 @@ pub struct WifiSettings {
     pub password: String,
 }
 
+/// Keyboard report sent to the host. 6KRO works everywhere, including BIOS setups,
+/// while NKRO lets any number of keys be held down at once.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum KeyRollover {
+    #[default]
+    #[serde(rename = "6KRO")]
+    SixKey,
+    #[serde(rename = "NKRO")]
+    NKey,
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)] // Default for easy creation
 pub struct DeviceSettings {
     // Add optional settings here
     pub wifi: Option<WifiSettings>,
     pub timezone_offset: Option<f32>,
     pub api_key: Option<String>,
+    pub long_press_ms: Option<u64>,
+    pub double_tap_ms: Option<u64>,
+    pub host_os: Option<HostOs>,
+    pub key_rollover: Option<KeyRollover>,
+    pub device_name: Option<String>,
+}
+
+pub const DEFAULT_TIMEZONE_OFFSET: f32 = 5.5;
+pub const DEFAULT_DEVICE_NAME: &str = "ESP DECK";
+
+impl DeviceSettings {
+    pub fn gesture_timing(&self) -> GestureTiming {
+        let mut timing = GestureTiming::default();
+        if let Some(long_press_ms) = self.long_press_ms {
+            timing.long_press = std::time::Duration::from_millis(long_press_ms);
+        }
+        if let Some(double_tap_ms) = self.double_tap_ms {
+            timing.double_tap = std::time::Duration::from_millis(double_tap_ms);
+        }
+        timing
+    }
+
+    pub fn device_name(&self) -> &str {
+        self.device_name.as_deref().unwrap_or(DEFAULT_DEVICE_NAME)
+    }
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
@@ -36,6 +76,19 @@ pub struct WidgetItemConfig {
     pub update_interval_seconds: u64,
 }
 
+/// A named grid of button mappings. The top level `mappings` and `button_names`
+/// of a DeviceConfig make up the first page, named `MAIN_PAGE_NAME`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct PageConfig {
+    pub name: String,
+    #[serde(default)]
+    pub mappings: MappingConfiguration,
+    #[serde(default, deserialize_with = "deserialize_usize_key_map")]
+    pub button_names: Option<HashMap<usize, String>>,
+}
+
+pub const MAIN_PAGE_NAME: &str = "Main";
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct DeviceConfig {
     pub settings: DeviceSettings,
@@ -49,8 +102,42 @@ pub struct DeviceConfig {
         deserialize_with = "deserialize_usize_optional_widget_item_map"
     )]
     pub widgets: Option<HashMap<usize, Option<WidgetItemConfig>>>,
+    // Additional pages after the main one. Configs without this field only have the main page.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pages: Option<Vec<PageConfig>>,
 }
 
+impl DeviceConfig {
+    /// Validates the mappings of the main page and of every extra page.
+    pub fn validate_mappings(&self) -> Vec<MappingError> {
+        let mut errors = Mapper::validate(&self.mappings, &self.settings);
+        let mut page_names = vec![MAIN_PAGE_NAME];
+        for page in self.pages.iter().flatten() {
+            // Pages are looked up by name, so a second page with the same name can't be reached
+            if page_names.contains(&page.name.as_str()) {
+                errors.push(MappingError {
+                    page: Some(page.name.clone()),
+                    button_id: String::new(),
+                    path: "name".to_string(),
+                    kind: MappingErrorKind::DuplicatePageName(page.name.clone()),
+                });
+            }
+            page_names.push(&page.name);
+            errors.extend(
+                Mapper::validate(&page.mappings, &self.settings)
+                    .into_iter()
+                    .map(|e| e.on_page(&page.name)),
+            );
+        }
+        errors
+    }
+}
+
+/// Returned by `Configurator::save` when the config it would end up with has invalid mappings.
+#[derive(Debug, Error)]
+#[error("Invalid mappings: {}", .0.iter().map(|e| e.to_string()).collect::<Vec<_>>().join("; "))]
+pub struct InvalidMappings(pub Vec<MappingError>);
+
 #[derive(Debug, Clone)]
 pub struct Configurator {
     // Config path shouldn't be needed outside of this module, so this
@@ -67,6 +154,11 @@ pub struct ConfigUpdatedFor {
     pub button_names: bool,
     pub api_key: bool,
     pub widgets: bool,
+    pub gesture_timing: bool,
+    pub host_os: bool,
+    pub key_rollover: bool,
+    pub device_name: bool,
+    pub pages: bool,
 }
 
 // Helper function to create a default configuration object
@@ -148,6 +240,7 @@ impl Configurator {
             mappings: crate::mapper::Mapper::load_default_config(),
             button_names: Some(button_names),
             widgets: Some(default_widgets_with_options),
+            pages: None,
         };
         log::info!("Creating default configuration file at {}", config_path);
 
@@ -195,12 +288,21 @@ impl Configurator {
         let mut merged_config_state = current_config_guard.clone();
 
         // Perform the merge. merged_config_state.widgets will be Option<HashMap<usize, Option<WidgetItemConfig>>>
+        let mut updated_for = ConfigUpdatedFor::default();
         Self::merge_configs(
             &mut merged_config_state,
             config_to_save_from_request,
-            config_updated_for,
+            &mut updated_for,
         );
 
+        // Validated after the merge, so mappings are checked against the settings and
+        // pages the device will actually use, not just the ones in the request
+        let mapping_errors = merged_config_state.validate_mappings();
+        if !mapping_errors.is_empty() {
+            return Err(InvalidMappings(mapping_errors).into());
+        }
+        *config_updated_for = updated_for;
+
         // Prepare a version for serialization: filter out None widget items.
         // The struct to be serialized should have widgets: Option<HashMap<usize, WidgetItemConfig>>
         #[derive(Serialize)]
@@ -209,6 +311,8 @@ impl Configurator {
             mappings: &'a MappingConfiguration,
             button_names: &'a Option<HashMap<usize, String>>,
             widgets: Option<HashMap<usize, WidgetItemConfig>>,
+            #[serde(skip_serializing_if = "Option::is_none")]
+            pages: &'a Option<Vec<PageConfig>>,
         }
 
         let widgets_to_serialize: Option<HashMap<usize, WidgetItemConfig>> = merged_config_state
@@ -235,6 +339,7 @@ impl Configurator {
             mappings: &merged_config_state.mappings,
             button_names: &merged_config_state.button_names,
             widgets: widgets_to_serialize,
+            pages: &merged_config_state.pages,
         };
 
         let json_data = serde_json::to_vec_pretty(&config_for_serialization)?;
@@ -289,6 +394,30 @@ impl Configurator {
             }
             config_updated_for.api_key = true;
         }
+        if let Some(new_long_press_ms) = new_config.settings.long_press_ms {
+            old_config.settings.long_press_ms = Some(new_long_press_ms);
+            config_updated_for.gesture_timing = true;
+        }
+        if let Some(new_double_tap_ms) = new_config.settings.double_tap_ms {
+            old_config.settings.double_tap_ms = Some(new_double_tap_ms);
+            config_updated_for.gesture_timing = true;
+        }
+        if let Some(new_host_os) = new_config.settings.host_os {
+            old_config.settings.host_os = Some(new_host_os);
+            config_updated_for.host_os = true;
+        }
+        if let Some(new_key_rollover) = new_config.settings.key_rollover {
+            old_config.settings.key_rollover = Some(new_key_rollover);
+            config_updated_for.key_rollover = true;
+        }
+        if let Some(new_device_name) = &new_config.settings.device_name {
+            if new_device_name.is_empty() {
+                old_config.settings.device_name = None;
+            } else {
+                old_config.settings.device_name = Some(new_device_name.clone());
+            }
+            config_updated_for.device_name = true;
+        }
         for (key, new_actions) in &new_config.mappings {
             if old_config.mappings.contains_key(key) {
                 old_config.mappings.insert(key.clone(), new_actions.clone());
@@ -311,6 +440,16 @@ impl Configurator {
             config_updated_for.button_names = true;
         }
 
+        // Pages are always replaced as a whole. An empty list removes all extra pages.
+        if let Some(new_pages) = &new_config.pages {
+            old_config.pages = if new_pages.is_empty() {
+                None
+            } else {
+                Some(new_pages.clone())
+            };
+            config_updated_for.pages = true;
+        }
+
         // Handle widget updates: add, update, or delete (if value is None)
         if let Some(incoming_widgets_map) = &new_config.widgets {
             // incoming_widgets_map is &HashMap<usize, Option<WidgetItemConfig>>
@@ -374,6 +513,11 @@ impl Configurator {
         config.settings.timezone_offset
     }
 
+    pub fn get_settings(&self) -> Option<DeviceSettings> {
+        let config = self.config_data.lock().ok()?;
+        Some(config.settings.clone())
+    }
+
     pub fn get_mappings(&self) -> Option<MappingConfiguration> {
         let config = self.config_data.lock().ok()?;
         Some(config.mappings.clone())
@@ -384,6 +528,20 @@ impl Configurator {
         config.button_names.clone()
     }
 
+    /// Returns all pages, with the top level mappings and button names as the first one.
+    pub fn get_pages(&self) -> Option<Vec<PageConfig>> {
+        let config = self.config_data.lock().ok()?;
+        let mut pages = vec![PageConfig {
+            name: MAIN_PAGE_NAME.to_string(),
+            mappings: config.mappings.clone(),
+            button_names: config.button_names.clone(),
+        }];
+        if let Some(extra_pages) = &config.pages {
+            pages.extend(extra_pages.iter().cloned());
+        }
+        Some(pages)
+    }
+
     pub fn get_api_key(&self) -> Option<String> {
         let config = self.config_data.lock().ok()?;
         config.settings.api_key.clone()
@@ -461,3 +619,133 @@ where
         None => Ok(None), // If the whole 'widgets' field was null or not present
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A fresh default config in its own file, so tests don't share state
+    fn configurator(name: &str) -> Configurator {
+        let path = std::env::temp_dir()
+            .join(format!("esp-deck-{}-{}", std::process::id(), name))
+            .join("device_config.json");
+        let _ = fs::remove_file(&path);
+        Configurator::load_or_create_default_config(path.to_str().unwrap()).unwrap()
+    }
+
+    fn request(json: &str) -> DeviceConfig {
+        serde_json::from_str(json).unwrap()
+    }
+
+    fn save(configurator: &Configurator, json: &str) -> Result<ConfigUpdatedFor> {
+        let mut updated_for = ConfigUpdatedFor::default();
+        configurator
+            .save(&request(json), &mut updated_for)
+            .map(|_| updated_for)
+    }
+
+    fn mapping_errors(result: Result<ConfigUpdatedFor>) -> Vec<MappingError> {
+        match result {
+            Err(e) => e.downcast::<InvalidMappings>().unwrap().0,
+            Ok(_) => panic!("config was saved"),
+        }
+    }
+
+    const UNICODE: &str =
+        r#"{"settings": {}, "mappings": {"1": [{"SendUnicode": {"text": "é"}}]}}"#;
+
+    #[test]
+    fn mappings_are_checked_against_stored_settings() {
+        let configurator = configurator("stored-settings");
+        let errors = mapping_errors(save(&configurator, UNICODE));
+        assert_eq!(errors[0].kind, MappingErrorKind::HostOsNotSet);
+
+        save(
+            &configurator,
+            r#"{"settings": {"host_os": "Linux"}, "mappings": {}}"#,
+        )
+        .unwrap();
+        let updated_for = save(&configurator, UNICODE).unwrap();
+        assert!(updated_for.mappings);
+        assert!(!updated_for.host_os);
+    }
+
+    #[test]
+    fn set_host_os_earlier_in_the_mapping_counts() {
+        let configurator = configurator("set-host-os");
+        save(
+            &configurator,
+            r#"{"settings": {}, "mappings": {"1": [
+                {"SetHostOs": {"host_os": "MacOs"}},
+                {"SendUnicode": {"text": "é"}}
+            ]}}"#,
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn settings_are_checked_against_stored_mappings() {
+        let configurator = configurator("stored-mappings");
+        let chord = r#"{"settings": {"key_rollover": "NKRO"}, "mappings": {}, "pages": [
+            {"name": "Chords", "mappings": {"1": [{"KeyPress": {"keys":
+                ["KeyA", "KeyB", "KeyC", "KeyD", "KeyE", "KeyF", "KeyG"], "modifier": null}}]}}
+        ]}"#;
+        save(&configurator, chord).unwrap();
+
+        let errors = mapping_errors(save(
+            &configurator,
+            r#"{"settings": {"key_rollover": "6KRO"}, "mappings": {}}"#,
+        ));
+        assert_eq!(errors[0].page.as_deref(), Some("Chords"));
+        assert_eq!(errors[0].kind, MappingErrorKind::TooManyKeys(7));
+        // Nothing of a rejected config is kept
+        let settings = configurator.get_settings().unwrap();
+        assert_eq!(settings.key_rollover, Some(KeyRollover::NKey));
+    }
+
+    #[test]
+    fn rejected_config_reports_every_error() {
+        let configurator = configurator("every-error");
+        let result = save(
+            &configurator,
+            r#"{"settings": {"timezone_offset": 1.0}, "mappings": {
+                "1": [{"KeyPress": {"keys": ["KeyQQ"], "modifier": null}}],
+                "2": [{"SendUnicode": {"text": "é"}}]
+            }}"#,
+        );
+        let e = result.unwrap_err();
+        assert!(e.to_string().starts_with("Invalid mappings: button 1, "));
+        assert_eq!(e.downcast::<InvalidMappings>().unwrap().0.len(), 2);
+        assert_eq!(configurator.get_timezone_offset(), None);
+    }
+
+    #[test]
+    fn duplicate_page_names_are_rejected() {
+        let configurator = configurator("duplicate-pages");
+        let errors = mapping_errors(save(
+            &configurator,
+            r#"{"settings": {}, "mappings": {}, "pages": [
+                {"name": "Media"}, {"name": "Media"}, {"name": "Main"}
+            ]}"#,
+        ));
+        let found: Vec<(Option<&str>, &str, &MappingErrorKind)> = errors
+            .iter()
+            .map(|e| (e.page.as_deref(), e.path.as_str(), &e.kind))
+            .collect();
+        assert_eq!(
+            found,
+            vec![
+                (
+                    Some("Media"),
+                    "name",
+                    &MappingErrorKind::DuplicatePageName("Media".to_string())
+                ),
+                (
+                    Some("Main"),
+                    "name",
+                    &MappingErrorKind::DuplicatePageName("Main".to_string())
+                ),
+            ]
+        );
+    }
+}
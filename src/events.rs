@@ -1,11 +1,15 @@
 use crate::{
-    bsp::usb_desc::{ConsumerReport, KeyboardReport, MouseReport},
+    bsp::usb_desc::{
+        AbsoluteMouseReport, ConsumerReport, GamepadReport, KeyboardLeds, KeyboardReport,
+        MouseReport, NkroKeyboardReport, RawReport, SystemReport,
+    },
+    config::{DeviceSettings, PageConfig},
     http_handlers::UserStatus,
-    mapper::MappingConfiguration,
 };
 use serde::{Deserialize, Serialize};
 use std::net::Ipv4Addr;
-use std::time::Duration;
+
+pub use esp_deck_core::hid_action::{HidAction, HttpMethod, UrlText, WebRequest};
 
 #[derive(Debug, Clone)]
 pub enum WifiStatus {
@@ -29,6 +33,12 @@ pub enum UsbHidCommand {
     SendKeyboard(KeyboardReport),
     SendMouse(MouseReport),
     SendConsumer(ConsumerReport),
+    SendKeyboardNkro(NkroKeyboardReport),
+    SendAbsoluteMouse(AbsoluteMouseReport),
+    SendSystem(SystemReport),
+    SendGamepad(GamepadReport),
+    SendMidi([u8; 4]), // USB-MIDI event packet
+    SendRaw(RawReport),
 }
 
 #[derive(Debug, Clone)]
@@ -53,23 +63,18 @@ pub enum AppEvent {
     TimeUpdate(TimeStatus),
     UsbUpdate(UsbStatus),
     UsbHidCommand(UsbHidCommand),
+    KeyboardLeds(KeyboardLeds),
     ButtonPressed(i32),
-    MappingUpdated(MappingConfiguration),
+    ButtonReleased(i32),
+    ButtonLatched(i32, bool),
+    ButtonLabelUpdate(i32, String),
+    MappingUpdated(Vec<PageConfig>),
+    PageSelected(usize),
+    PageChanged(usize, Vec<i32>), // page index, IDs of the latched buttons on it
+    SettingsUpdated(DeviceSettings),
     UserStatusUpdate(UserStatus),
     HttpServerUpdate(String),
+    StatusMessage(String),
+    TextFetched(i32, Result<String, String>), // button ID, text to type or why there is none
     ServerWidgetUpdate(ServerWidgetData),
 }
-
-// Represents a single primitive HID action or delay
-#[derive(Debug, Clone)]
-pub enum HidAction {
-    KeyPress(u8, [u8; 6]), // modifier, up to 6 keycodes
-    KeyRelease,            // Releases all keys/modifiers
-    MouseMove(i8, i8),     // dx, dy
-    MousePress(u8),        // buttons bitmask
-    MouseRelease,          // Releases all buttons
-    MouseWheel(i8),        // wheel movement
-    ConsumerPress(u16),    // usage_id
-    ConsumerRelease,       // Releases consumer control
-    Delay(Duration),       // Pause execution
-}
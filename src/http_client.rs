@@ -1,5 +1,7 @@
+use crate::events::HttpMethod;
 use anyhow::Result;
 use embedded_svc::http::client::{Client, Method};
+use embedded_svc::io::Write;
 use esp_idf_svc::http::client::{Configuration, EspHttpConnection};
 use std::sync::mpsc::{channel, Sender};
 use std::thread;
@@ -22,16 +24,35 @@ impl HttpClient {
         })
     }
 
-    pub fn get(&mut self, url: &str, headers: Option<&Vec<(String, String)>>) -> Result<Vec<u8>> {
-        let headers_processed = match headers {
-            Some(h) => h
+    pub fn request(
+        &mut self,
+        method: Method,
+        url: &str,
+        headers: &[(String, String)],
+        body: Option<&[u8]>,
+        max_body_len: Option<usize>,
+    ) -> Result<HttpResponse> {
+        let content_length = body.map(|body| body.len().to_string());
+        let mut headers_processed = headers
+            .iter()
+            .map(|(k, v)| (k.as_str(), v.as_str()))
+            .collect::<Vec<(&str, &str)>>();
+        // Without a length the body would be sent chunked, which not every server takes
+        if let Some(content_length) = &content_length {
+            if !headers_processed
                 .iter()
-                .map(|(k, v)| (k.as_str(), v.as_str()))
-                .collect::<Vec<(&str, &str)>>(),
-            None => vec![("accept", "text/plain")],
-        };
-        let request = self.client.request(Method::Get, url, &headers_processed)?;
+                .any(|(k, _)| k.eq_ignore_ascii_case("content-length"))
+            {
+                headers_processed.push(("content-length", content_length));
+            }
+        }
+        let mut request = self.client.request(method, url, &headers_processed)?;
+        if let Some(body) = body {
+            request.write_all(body)?;
+            request.flush()?;
+        }
         let mut response = request.submit()?;
+        let status = response.status();
 
         let mut response_bytes = Vec::new();
         let mut buf = [0; 1024];
@@ -41,18 +62,50 @@ impl HttpClient {
                 Ok(0) => break,
                 Ok(bytes_read_count) => {
                     response_bytes.extend_from_slice(&buf[..bytes_read_count]);
+                    if let Some(max) = max_body_len.filter(|max| response_bytes.len() > *max) {
+                        return Err(anyhow::anyhow!(
+                            "Response with status {} is larger than {} bytes",
+                            status,
+                            max
+                        ));
+                    }
                 }
                 Err(e) => return Err(anyhow::Error::from(e)),
             }
         }
 
-        Ok(response_bytes)
+        Ok(HttpResponse {
+            status,
+            body: response_bytes,
+        })
+    }
+}
+
+impl From<HttpMethod> for Method {
+    fn from(method: HttpMethod) -> Self {
+        match method {
+            HttpMethod::Get => Method::Get,
+            HttpMethod::Post => Method::Post,
+            HttpMethod::Put => Method::Put,
+            HttpMethod::Patch => Method::Patch,
+            HttpMethod::Delete => Method::Delete,
+        }
     }
 }
 
+pub struct HttpResponse {
+    pub status: u16,
+    pub body: Vec<u8>,
+}
+
 pub struct HttpRequest {
+    pub method: Method,
     pub url: String,
-    pub response_tx: Sender<Result<Vec<u8>>>,
+    pub headers: Vec<(String, String)>,
+    pub body: Option<Vec<u8>>,
+    // Requests with a bigger response fail instead of reading all of it
+    pub max_body_len: Option<usize>,
+    pub on_response: Box<dyn FnOnce(Result<HttpResponse>) + Send>,
 }
 
 pub struct HttpClientPool {
@@ -67,12 +120,18 @@ impl HttpClientPool {
                 let mut client = match HttpClient::new() {
                     Ok(c) => c,
                     Err(e) => {
-                        let _ = req.response_tx.send(Err(e));
+                        (req.on_response)(Err(e));
                         continue;
                     }
                 };
-                let result = client.get(&req.url, None);
-                let _ = req.response_tx.send(result);
+                let result = client.request(
+                    req.method,
+                    &req.url,
+                    &req.headers,
+                    req.body.as_deref(),
+                    req.max_body_len,
+                );
+                (req.on_response)(result);
             }
         });
         Self { request_tx }
@@ -87,11 +146,16 @@ impl HttpClientPool {
 
     pub fn get_bytes(&self, url: &str) -> Result<Vec<u8>> {
         let (tx, rx) = channel();
-        let req = HttpRequest {
+        self.submit(HttpRequest {
+            method: Method::Get,
             url: url.to_string(),
-            response_tx: tx,
-        };
-        self.request_tx.send(req).unwrap();
+            headers: vec![("accept".to_string(), "text/plain".to_string())],
+            body: None,
+            max_body_len: None,
+            on_response: Box::new(move |result| {
+                let _ = tx.send(result.map(|response| response.body));
+            }),
+        })?;
         match rx.recv() {
             Ok(result) => match result {
                 Ok(bytes) => Ok(bytes),
@@ -101,7 +165,17 @@ impl HttpClientPool {
         }
     }
 
-    // Optionally, add async or callback-based API here
+    /// Queues a request without waiting for it. `on_response` runs on the pool's
+    /// thread once the request is done.
+    ///
+    /// The pool has a single thread, which runs requests one at a time. Button actions
+    /// share it with the widgets' blocking `get` and `get_bytes` calls, so a slow server
+    /// on either side holds up the other.
+    pub fn submit(&self, request: HttpRequest) -> Result<()> {
+        self.request_tx
+            .send(request)
+            .map_err(|_| anyhow::anyhow!("HTTP client pool is not running"))
+    }
 }
 
 impl Default for HttpClientPool {
@@ -7,5 +7,12 @@ pub mod http_handlers;
 pub mod http_server;
 pub mod mapper;
 pub mod protocol;
+pub mod raw_hid;
 pub mod ui;
 pub mod usb_hid_client;
+
+// Modules that don't need ESP-IDF, in the esp-deck-core crate so their tests run on the host
+pub use esp_deck_core::{
+    consumer_usage, fetched_text, gesture, keyboard_layout, macros, midi, osc, text_template, udp,
+    unicode_input, wake_on_lan,
+};
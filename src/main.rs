@@ -3,7 +3,7 @@ use esp_deck::http_handlers;
 use esp_deck::{
     actor::Actor,
     bsp::{time, usb::Usb, wifi::Wifi},
-    config::{Configurator, WifiSettings},
+    config::{Configurator, WifiSettings, DEFAULT_TIMEZONE_OFFSET},
     events::{AppEvent, WifiStatus},
     http_server::start_http_server,
     mapper::Mapper,
@@ -29,10 +29,11 @@ use std::{
     thread,
 };
 
-const TZ_OFFSET: f32 = 5.5;
 const VFS_BASE_PATH: &str = "/littlefs";
 const CONFIG_PATH: &str = "/littlefs/device_config.json";
 const PARTITION_LABEL: &str = "storage";
+// HID reports waiting for the USB thread. Macros wait once this many are queued.
+const USB_HID_QUEUE_LEN: usize = 8;
 
 /// Mounts the LittleFS partition using the underlying C API.
 fn init_vfs() -> anyhow::Result<()> {
@@ -118,13 +119,18 @@ fn main() -> anyhow::Result<()> {
     // Actor would take action on events typically from the UI thread. (e.g. when a button is pressed, or a new config is received)
     // and it sends events to the underlying USB module
     let (actor_tx, actor_rx): (Sender<AppEvent>, Receiver<AppEvent>) = mpsc::channel();
-    let (usb_hid_tx, usb_hid_rx): (Sender<AppEvent>, Receiver<AppEvent>) = mpsc::channel();
+    let (usb_hid_tx, usb_hid_rx): (SyncSender<AppEvent>, Receiver<AppEvent>) =
+        mpsc::sync_channel(USB_HID_QUEUE_LEN);
     let (usb_message_tx, usb_message_rx): (Sender<Vec<u8>>, Receiver<Vec<u8>>) = mpsc::channel();
     let (main_wifi_time_init_tx, main_wifi_time_init_rx): (
         SyncSender<Option<WifiSettings>>,
         Receiver<Option<WifiSettings>>,
     ) = mpsc::sync_channel(1);
 
+    // TODO: Get a signal from wifi to http pool to start serving requests
+    // Created before the small thread stack size below is set, TLS needs more
+    let http_pool = Arc::new(HttpClientPool::new());
+
     ThreadSpawnConfiguration {
         stack_size: 4096,
         ..Default::default()
@@ -138,6 +144,7 @@ fn main() -> anyhow::Result<()> {
     let wifi_sys_loop = sys_loop;
     let wifi_timer = timer_service.clone();
     let peripheral_update_tx = ui_updates_tx.clone();
+    let wifi_actor_tx = actor_tx.clone();
     let wifi_modem = peripherals.modem;
     if let Err(e) = main_wifi_time_init_tx.send(wifi_settings) {
         log::error!("Failed to send wifi settings: {}", e);
@@ -154,6 +161,7 @@ fn main() -> anyhow::Result<()> {
             wifi_nvs,
             wifi_timer,
             peripheral_update_tx.clone(),
+            wifi_actor_tx.clone(),
         )) {
             Ok(driver) => driver,
             Err(e) => {
@@ -202,9 +210,9 @@ fn main() -> anyhow::Result<()> {
                             ));
                         }
                         log::error!("Wi-Fi connection failed: {}", e);
-                        if let Err(e2) = peripheral_update_tx
-                            .send(AppEvent::WifiUpdate(WifiStatus::Error(e.to_string())))
-                        {
+                        let status = WifiStatus::Error(e.to_string());
+                        let _ = wifi_actor_tx.send(AppEvent::WifiUpdate(status.clone()));
+                        if let Err(e2) = peripheral_update_tx.send(AppEvent::WifiUpdate(status)) {
                             log::error!("Failed to send WiFi error update: {}", e2);
                         }
                     }
@@ -216,24 +224,41 @@ fn main() -> anyhow::Result<()> {
         }
     }));
 
-    let actor_mappings = match config.get_mappings() {
-        Some(m) => m,
+    let pages = match config.get_pages() {
+        Some(p) => p,
         None => {
             log::error!("Failed to get mappings from config");
             return Err(anyhow::anyhow!("Failed to get mappings from config"));
         }
     };
-    let actor_mapper = Mapper::new(actor_mappings);
+    let actor_mapper = Mapper::new(pages.clone());
+    let actor_settings = config.get_settings().unwrap_or_default();
     let actor_usb_hid_tx = usb_hid_tx.clone();
+    let actor_ui_tx = ui_updates_tx.clone();
+    let actor_http_pool = http_pool.clone();
+    let actor_self_tx = actor_tx.clone();
     threads.push(thread::spawn(move || {
-        let mut actor = Actor::new(actor_rx, actor_usb_hid_tx, actor_mapper);
+        let mut actor = Actor::new(
+            actor_rx,
+            actor_self_tx,
+            actor_usb_hid_tx,
+            actor_ui_tx,
+            actor_mapper,
+            actor_settings,
+            actor_http_pool,
+        );
         actor.run();
     }));
 
     let usb_updates_tx = ui_updates_tx.clone();
     let usb_message_tx = usb_message_tx.clone();
+    let usb_hid_output_tx = actor_tx.clone();
     threads.push(thread::spawn(move || {
-        let _usb = Usb::new(usb_updates_tx.clone(), usb_message_tx.clone());
+        let _usb = Usb::new(
+            usb_updates_tx.clone(),
+            usb_message_tx.clone(),
+            usb_hid_output_tx,
+        );
         if let Err(e) = UsbHidClient::run(usb_hid_rx) {
             log::error!("UsbHidClient::run failed: {}", e);
         }
@@ -242,11 +267,12 @@ fn main() -> anyhow::Result<()> {
         log::error!("Failed to set thread spawn configuration: {}", e);
     }
 
-    // Get the TZ offset, widgets config, button names here because we move the config into
+    // Get the TZ offset, widgets config here because we move the config into
     // the ProtocolManager past this point
-    let tz_offset = config.get_timezone_offset().unwrap_or(TZ_OFFSET);
+    let tz_offset = config
+        .get_timezone_offset()
+        .unwrap_or(DEFAULT_TIMEZONE_OFFSET);
     let widgets = config.get_widgets();
-    let button_names = config.get_button_names();
 
     // Note: api_key for http server is already fetched and cloned above (http_server_api_key)
     // config object will be moved into ProtocolManager thread now.
@@ -288,14 +314,12 @@ fn main() -> anyhow::Result<()> {
         }
     });
 
-    // TODO: Get a signal from wifi to http pool to start serving requests
-    let http_pool = Arc::new(HttpClientPool::new());
     let _ = Window::init(
         touch_i2c,
         ui_updates_rx,
         actor_tx,
         tz_offset,
-        button_names,
+        pages,
         http_pool,
         widgets,
     );
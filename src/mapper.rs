@@ -1,9 +1,22 @@
-use crate::events::HidAction;
+use crate::bsp::usb_desc::{KeyboardLeds, ABSOLUTE_MOUSE_MAX, GAMEPAD_BUTTONS, RAW_REPORT_LEN};
+use crate::config::{DeviceSettings, HostOs, KeyRollover, PageConfig};
+use crate::consumer_usage::ConsumerUsage;
+use crate::events::{HidAction, HttpMethod, UrlText, WebRequest};
+use crate::gesture::GestureBindings;
+use crate::keyboard_layout::{KeyStroke, KeyboardLayout};
+use crate::macros::{MacroOp, HOLD_REPEAT_INTERVAL};
+use crate::midi::MidiMessage;
+use crate::osc::{self, OscArg};
+use crate::text_template::{self, TemplateError, TemplateValues};
+use crate::unicode_input::unicode_sequence;
+use crate::wake_on_lan;
 use keycode::{KeyMap, KeyMappingCode};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::net::Ipv4Addr;
 use std::str::FromStr;
-use std::time::Duration; // Add serde for future file loading // Make sure this path is correct
+use std::time::{Duration, SystemTime}; // Add serde for future file loading // Make sure this path is correct
+use thiserror::Error;
 
 // --- Configuration Structures ---
 // These structs define how the mapping is represented, potentially loaded from a file later.
@@ -19,6 +32,15 @@ pub enum ConfigAction {
         dx: i8,
         dy: i8,
     },
+    MouseGlide {
+        dx: i32,
+        dy: i32,
+        duration_ms: u64,
+    }, // Moves the pointer by any distance, spread evenly over `duration_ms`
+    MouseMoveTo {
+        x: f32,
+        y: f32,
+    }, // Moves the pointer to a screen position, from 0.0 (left/top edge) to 1.0 (right/bottom edge)
     MousePress {
         button: u8,
     }, // Button bitmask (1=Left, 2=Right, 4=Middle)
@@ -26,10 +48,89 @@ pub enum ConfigAction {
     MouseWheel {
         amount: i8,
     },
+    MouseHScroll {
+        amount: i8,
+    }, // Scrolls sideways, positive amounts scroll right
+    Drag {
+        button: u8,
+        path: Vec<Waypoint>,
+    }, // Holds `button` down while gliding through each waypoint in turn
     ConsumerPress {
-        usage_id: u16,
-    },
+        usage_id: ConsumerUsage,
+    }, // Usage ID on the Consumer page, as a number or a name like "VolumeIncrement"
     ConsumerRelease,
+    SystemPress {
+        control: SystemControl,
+    },
+    SystemRelease,
+    GamepadPress {
+        button: u8,
+    }, // Gamepad button, 1 to 32
+    GamepadRelease {
+        #[serde(default)]
+        button: Option<u8>,
+    }, // Releases one gamepad button, or all of them along with centering the axes and hat
+    GamepadAxis {
+        axis: GamepadAxis,
+        value: i8,
+    }, // Holds an axis at a position, -127 to 127
+    GamepadHat {
+        direction: HatDirection,
+    },
+    MidiNoteOn {
+        channel: u8,
+        note: u8,
+        velocity: u8,
+    }, // Channels are 1 to 16, notes and velocities 0 to 127
+    MidiNoteOff {
+        channel: u8,
+        note: u8,
+    },
+    MidiCc {
+        channel: u8,
+        controller: u8,
+        value: u8,
+    },
+    MidiProgramChange {
+        channel: u8,
+        program: u8,
+    },
+    RawReport {
+        bytes: Vec<u8>,
+    }, // Sends up to 32 bytes on the vendor defined report, for host applications using hidapi
+    HttpRequest {
+        #[serde(default)]
+        method: HttpMethod,
+        url: String,
+        #[serde(default)]
+        headers: HashMap<String, String>,
+        #[serde(default)]
+        body: Option<String>,
+    }, // Sends the request from the deck itself, the response status goes to the status log
+    Osc {
+        host: String,
+        port: u16,
+        address: String,
+        #[serde(default)]
+        args: Vec<OscArg>,
+    }, // Sends an OSC message over UDP, for lighting and audio consoles
+    UdpSend {
+        host: String,
+        port: u16,
+        payload: UdpPayload,
+    }, // Sends a single UDP datagram
+    WakeOnLan {
+        mac: String,
+        #[serde(default)]
+        broadcast: Option<String>,
+    }, // Sends the magic packet for `mac` to `broadcast` (255.255.255.255 if not given)
+    TypeFromUrl {
+        url: String,
+        #[serde(default)]
+        json_pointer: Option<String>,
+        #[serde(default)]
+        layout: Option<String>,
+    }, // Fetches text, or the value at `json_pointer` of a JSON response, and types it once the button is pressed again
     Delay {
         ms: u64,
     },
@@ -37,29 +138,438 @@ pub enum ConfigAction {
         keys: Vec<String>,
         modifiers: Vec<String>,
     },
+    SendText {
+        text: String,
+        layout: Option<String>,
+    }, // Types the text as it would be typed on the host's keyboard layout (US if not given), after filling in placeholders like {date} or {counter:1}. Counters start again at 1 when the device restarts
+    SendUnicode {
+        text: String,
+    }, // Types each character by its code point, using the input method of the configured host OS
     Sequence(Vec<ConfigAction>), // Represents a macro
+    StopAll, // Cancels the macros running on all other buttons and releases everything
+    StopButton {
+        button_id: i32,
+    }, // Cancels the macros running on a button and releases everything
+    Repeat {
+        count: u32,
+        actions: Vec<ConfigAction>,
+    }, // Runs `actions` `count` times
+    RepeatWhileHeld {
+        interval_ms: u64,
+        actions: Vec<ConfigAction>,
+    }, // Runs `actions` over and over until the button is released, waiting `interval_ms` (at least 50) in between
+    Toggle {
+        on: Vec<ConfigAction>,
+        off: Vec<ConfigAction>,
+    }, // Alternates between `on` and `off` on each trigger, starting with `on`
+    IfLed {
+        led: KeyboardLed,
+        #[serde(default)]
+        on: Vec<ConfigAction>,
+        #[serde(default)]
+        off: Vec<ConfigAction>,
+    }, // Runs `on` or `off` depending on whether the host has the keyboard LED lit
+    SetHostOs {
+        host_os: HostOs,
+    }, // Switches the host OS used for Primary and SendUnicode. The switch is temporary and not saved, the host_os setting applies again after a restart or a settings change
+    SwitchPage {
+        page: String,
+    }, // Shows the named page
+    PushPage {
+        page: String,
+    }, // Shows the named page, remembering the current one for PopPage
+    PopPage, // Goes back to the page shown before the last PushPage
+}
+
+impl ConfigAction {
+    /// Returns the action lists nested inside this action.
+    fn nested_actions(&self) -> Vec<&[ConfigAction]> {
+        match self {
+            ConfigAction::Sequence(actions)
+            | ConfigAction::Repeat { actions, .. }
+            | ConfigAction::RepeatWhileHeld { actions, .. } => vec![actions],
+            ConfigAction::Toggle { on, off } | ConfigAction::IfLed { on, off, .. } => {
+                vec![on, off]
+            }
+            _ => Vec::new(),
+        }
+    }
+}
+
+/// Controls of the System Control report, numbered as the report sends them.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub enum SystemControl {
+    PowerDown = 1,
+    Sleep = 2,
+    WakeUp = 3,
+}
+
+/// Keyboard LEDs, numbered by their bit in the LED output report.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub enum KeyboardLed {
+    NumLock = 0,
+    CapsLock = 1,
+    ScrollLock = 2,
+    Compose = 3,
+    Kana = 4,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub enum GamepadAxis {
+    X = 0,
+    Y = 1,
+}
+
+/// Directions of the gamepad hat switch, numbered clockwise from up as the report sends them.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub enum HatDirection {
+    Up = 0,
+    UpRight = 1,
+    Right = 2,
+    DownRight = 3,
+    Down = 4,
+    DownLeft = 5,
+    Left = 6,
+    UpLeft = 7,
+    Centered = 8,
+}
+
+/// Contents of a UdpSend datagram, either text sent as UTF-8 or a list of bytes.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(untagged)]
+pub enum UdpPayload {
+    Text(String),
+    Bytes(Vec<u8>),
+}
+
+impl UdpPayload {
+    fn into_bytes(self) -> Vec<u8> {
+        match self {
+            UdpPayload::Text(text) => text.into_bytes(),
+            UdpPayload::Bytes(bytes) => bytes,
+        }
+    }
+}
+
+/// A point on a Drag path, relative to the previous one.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Waypoint {
+    pub dx: i32,
+    pub dy: i32,
+    #[serde(default)]
+    pub duration_ms: u64,
+}
+
+/// Actions bound to a single button.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(untagged)]
+pub enum ButtonMapping {
+    /// Plain list of actions, played in one go when the button is pressed.
+    Actions(Vec<ConfigAction>),
+    /// Separate action lists for each phase of a button press.
+    Phases(ButtonPhases),
+}
+
+/// When `long_press` or `double_tap` is set, the button is classified by gesture instead:
+/// a tap plays `on_press` followed by `on_release`, and `while_held` is not used.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct ButtonPhases {
+    /// Played once when the button goes down.
+    #[serde(default)]
+    pub on_press: Vec<ConfigAction>,
+    /// Played repeatedly for as long as the button stays down.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub while_held: Vec<ConfigAction>,
+    /// Played once when the button comes back up.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub on_release: Vec<ConfigAction>,
+    /// Played when the button is held past the long press threshold.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub long_press: Option<Vec<ConfigAction>>,
+    /// Played when the button is tapped twice within the double tap window.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub double_tap: Option<Vec<ConfigAction>>,
+}
+
+impl ButtonMapping {
+    /// Returns the actions configured for the given phase of a button press.
+    pub fn actions_for(&self, phase: ButtonPhase) -> &[ConfigAction] {
+        match (self, phase) {
+            (ButtonMapping::Actions(actions), ButtonPhase::Press) => actions,
+            (ButtonMapping::Actions(_), _) => &[],
+            (ButtonMapping::Phases(phases), ButtonPhase::Press) => &phases.on_press,
+            (ButtonMapping::Phases(phases), ButtonPhase::Hold) => &phases.while_held,
+            (ButtonMapping::Phases(phases), ButtonPhase::Release) => &phases.on_release,
+            (ButtonMapping::Phases(phases), ButtonPhase::LongPress) => {
+                phases.long_press.as_deref().unwrap_or_default()
+            }
+            (ButtonMapping::Phases(phases), ButtonPhase::DoubleTap) => {
+                phases.double_tap.as_deref().unwrap_or_default()
+            }
+        }
+    }
+
+    /// Returns every action list of the mapping, named as they appear in the config.
+    fn action_lists(&self) -> Vec<(&'static str, &[ConfigAction])> {
+        match self {
+            ButtonMapping::Actions(actions) => vec![("", actions)],
+            ButtonMapping::Phases(phases) => {
+                let mut lists: Vec<(&'static str, &[ConfigAction])> = vec![
+                    ("on_press", &phases.on_press),
+                    ("while_held", &phases.while_held),
+                    ("on_release", &phases.on_release),
+                ];
+                if let Some(long_press) = &phases.long_press {
+                    lists.push(("long_press", long_press));
+                }
+                if let Some(double_tap) = &phases.double_tap {
+                    lists.push(("double_tap", double_tap));
+                }
+                lists
+            }
+        }
+    }
+
+    /// Returns true if the mapping has a Toggle action, nested ones included.
+    pub fn has_toggle(&self) -> bool {
+        fn any_toggle(actions: &[ConfigAction]) -> bool {
+            actions.iter().any(|action| {
+                matches!(action, ConfigAction::Toggle { .. })
+                    || action.nested_actions().into_iter().any(any_toggle)
+            })
+        }
+        self.action_lists()
+            .into_iter()
+            .any(|(_, actions)| any_toggle(actions))
+    }
+
+    pub fn gesture_bindings(&self) -> GestureBindings {
+        match self {
+            ButtonMapping::Actions(_) => GestureBindings::default(),
+            ButtonMapping::Phases(phases) => GestureBindings {
+                long_press: phases.long_press.is_some(),
+                double_tap: phases.double_tap.is_some(),
+            },
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ButtonPhase {
+    Press,
+    Hold,
+    Release,
+    LongPress,
+    DoubleTap,
 }
 
 // Define the type alias publicly here
-pub type MappingConfiguration = HashMap<String, Vec<ConfigAction>>;
+pub type MappingConfiguration = HashMap<String, ButtonMapping>;
+
+/// A problem with a single action in a mapping, found by `Mapper::validate`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct MappingError {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub page: Option<String>,
+    /// Key of the mapping, a button ID or "default". Empty for errors about the page itself.
+    #[serde(rename = "buttonId")]
+    pub button_id: String,
+    /// Where the action sits in the mapping, like `on_press[1].Sequence[0].KeyPress.keys[2]`
+    pub path: String,
+    pub kind: MappingErrorKind,
+}
+
+impl std::fmt::Display for MappingError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if let Some(page) = &self.page {
+            write!(f, "page '{}', ", page)?;
+        }
+        if !self.button_id.is_empty() {
+            write!(f, "button {}, ", self.button_id)?;
+        }
+        write!(f, "{}: {}", self.path, self.kind)
+    }
+}
+
+impl std::error::Error for MappingError {}
+
+impl MappingError {
+    pub fn on_page(mut self, page: &str) -> Self {
+        self.page = Some(page.to_string());
+        self
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Error)]
+pub enum MappingErrorKind {
+    #[error("invalid key name '{0}'")]
+    InvalidKey(String),
+    #[error("invalid modifier name '{0}'")]
+    InvalidModifier(String),
+    #[error("more than 6 keys in one KeyPress ({0}), set key_rollover to NKRO for larger chords")]
+    TooManyKeys(usize),
+    #[error("{keys} keys but {modifiers} modifiers")]
+    MismatchedSendString { keys: usize, modifiers: usize },
+    #[error("{0}")]
+    Text(String),
+    #[error("{0} is outside the screen, use 0.0 to 1.0")]
+    OutOfScreen(String),
+    #[error("there is no gamepad button {0}, use 1 to 32")]
+    InvalidGamepadButton(u8),
+    #[error("{value} is out of range, use {min} to {max}")]
+    OutOfRange { value: u8, min: u8, max: u8 },
+    #[error("{0} bytes do not fit in a raw report of 32 bytes")]
+    RawReportTooLong(usize),
+    #[error("'{0}' is not an http:// or https:// URL")]
+    InvalidUrl(String),
+    #[error("OSC address '{0}' must start with '/'")]
+    InvalidOscAddress(String),
+    #[error("invalid MAC address '{0}'")]
+    InvalidMacAddress(String),
+    #[error("JSON pointer '{0}' must start with '/'")]
+    InvalidJsonPointer(String),
+    #[error("unknown consumer usage '{0}'")]
+    UnknownConsumerUsage(String),
+    #[error("interval of {ms} ms is too short, use at least {min} ms")]
+    IntervalTooShort { ms: u64, min: u64 },
+    #[error("{0} only takes effect once, so it can't be repeated")]
+    InRepeat(String),
+    #[error("host_os is not set in settings")]
+    HostOsNotSet,
+    #[error("there is already a page named '{0}'")]
+    DuplicatePageName(String),
+}
 
 // --- Mapper Implementation ---
 
+// Logical modifier for shortcuts, Cmd on macOS and Ctrl everywhere else
+const PRIMARY_KEY: &str = "Primary";
+
 pub struct Mapper {
-    config: MappingConfiguration,
-    // TODO: Add state if needed for complex macros/toggles later
+    pages: Vec<PageConfig>,
+    current_page: usize,
+    // Pages to go back to with PopPage, most recent last
+    page_stack: Vec<usize>,
+    // Latched state of buttons with a Toggle action, keyed by page index and button ID.
+    // A button has a single latch, shared by all Toggle actions bound to it.
+    toggle_states: HashMap<(usize, i32), bool>,
+    host_os: Option<HostOs>,
+    // Last LED state the host sent, for IfLed
+    keyboard_leds: KeyboardLeds,
+    // Values for the placeholders in SendText
+    template_values: TemplateValues,
 }
 
 impl Mapper {
-    /// Creates a new Mapper instance with the provided configuration.
-    pub fn new(config: MappingConfiguration) -> Self {
+    /// Creates a new Mapper instance with the provided pages. The first page is shown initially.
+    pub fn new(pages: Vec<PageConfig>) -> Self {
         // Remove internal call to load_default_config
         // let default_config = Self::load_default_config();
-        Self { config }
+        Self {
+            pages,
+            current_page: 0,
+            page_stack: Vec::new(),
+            toggle_states: HashMap::new(),
+            host_os: None,
+            keyboard_leds: KeyboardLeds::default(),
+            template_values: TemplateValues::default(),
+        }
+    }
+
+    /// Replaces the pages. Latches of buttons that no longer have a Toggle action are
+    /// dropped, and the IDs of the ones that were latched on the current page returned.
+    pub fn update_mapping_config(&mut self, pages: Vec<PageConfig>) -> Vec<i32> {
+        self.pages = pages;
+        if self.current_page >= self.pages.len() {
+            self.current_page = 0;
+        }
+        self.page_stack.retain(|page| *page < self.pages.len());
+        let mut unlatched = Vec::new();
+        let pages = &self.pages;
+        let current_page = self.current_page;
+        self.toggle_states.retain(|(page, button_id), latched| {
+            let keep = pages.get(*page).is_some_and(|p| {
+                p.mappings
+                    .get(&button_id.to_string())
+                    .or_else(|| p.mappings.get("default"))
+                    .is_some_and(ButtonMapping::has_toggle)
+            });
+            if !keep && *latched && *page == current_page {
+                unlatched.push(*button_id);
+            }
+            keep
+        });
+        unlatched
+    }
+
+    pub fn set_host_os(&mut self, host_os: Option<HostOs>) {
+        self.host_os = host_os;
+    }
+
+    pub fn host_os(&self) -> Option<HostOs> {
+        self.host_os
     }
 
-    pub fn update_mapping_config(&mut self, config: MappingConfiguration) {
-        self.config = config;
+    pub fn set_keyboard_leds(&mut self, leds: KeyboardLeds) {
+        self.keyboard_leds = leds;
+    }
+
+    pub fn set_timezone_offset(&mut self, timezone_offset: f32) {
+        self.template_values.timezone_offset = timezone_offset;
+    }
+
+    pub fn set_device_name(&mut self, device_name: &str) {
+        self.template_values.device_name = device_name.to_string();
+    }
+
+    pub fn set_ip_address(&mut self, ip: Option<Ipv4Addr>) {
+        self.template_values.ip = ip;
+    }
+
+    pub fn current_page(&self) -> usize {
+        self.current_page
+    }
+
+    /// Shows the page at the given index. Returns false if there is no such page.
+    pub fn switch_page(&mut self, page: usize) -> bool {
+        if page >= self.pages.len() {
+            log::warn!("Page {} does not exist", page);
+            return false;
+        }
+        self.current_page = page;
+        true
+    }
+
+    /// Returns whether the Toggle action on the given button is currently latched on.
+    pub fn is_latched(&self, page: usize, button_id: i32) -> bool {
+        self.toggle_states
+            .get(&(page, button_id))
+            .copied()
+            .unwrap_or(false)
+    }
+
+    /// Returns the IDs of all latched buttons on the given page.
+    pub fn latched_buttons(&self, page: usize) -> Vec<i32> {
+        self.toggle_states
+            .iter()
+            .filter(|((latch_page, _), latched)| *latch_page == page && **latched)
+            .map(|((_, button_id), _)| *button_id)
+            .collect()
+    }
+
+    fn page_index(&self, name: &str) -> Option<usize> {
+        let index = self.pages.iter().position(|page| page.name == name);
+        if index.is_none() {
+            log::warn!("Page '{}' does not exist", name);
+        }
+        index
+    }
+
+    fn mapping(&self, page: usize, button_id: i32) -> Option<&ButtonMapping> {
+        let mappings = &self.pages.get(page)?.mappings;
+        mappings
+            .get(&button_id.to_string())
+            .or_else(|| mappings.get("default")) // Fallback to default if specific ID not found
     }
 
     pub fn get_default_button_names() -> Vec<&'static str> {
@@ -86,7 +596,7 @@ impl Mapper {
     /// Loads a hardcoded default configuration - Keep as helper, maybe make pub?
     /// Or move this logic entirely into config.rs's DeviceConfiguration::default_config()
     pub fn load_default_config() -> MappingConfiguration {
-        let mut config = HashMap::new();
+        let mut config: HashMap<String, Vec<ConfigAction>> = HashMap::new();
 
         // === Keyboard Examples ===
 
@@ -203,7 +713,9 @@ impl Mapper {
         config.insert(
             "9".to_string(), // Use "default" for unassigned buttons
             vec![
-                ConfigAction::ConsumerPress { usage_id: 0xE9 }, // Volume Increment
+                ConfigAction::ConsumerPress {
+                    usage_id: ConsumerUsage::Id(0xE9),
+                }, // Volume Increment
                 ConfigAction::Delay { ms: 10 },
                 ConfigAction::ConsumerRelease,
             ],
@@ -213,7 +725,9 @@ impl Mapper {
         config.insert(
             "10".to_string(),
             vec![
-                ConfigAction::ConsumerPress { usage_id: 0xEA }, // Volume Decrement
+                ConfigAction::ConsumerPress {
+                    usage_id: ConsumerUsage::Id(0xEA),
+                }, // Volume Decrement
                 ConfigAction::Delay { ms: 10 },
                 ConfigAction::ConsumerRelease,
             ],
@@ -223,7 +737,9 @@ impl Mapper {
         config.insert(
             "11".to_string(),
             vec![
-                ConfigAction::ConsumerPress { usage_id: 0xE2 }, // Mute
+                ConfigAction::ConsumerPress {
+                    usage_id: ConsumerUsage::Id(0xE2),
+                }, // Mute
                 ConfigAction::Delay { ms: 10 },
                 ConfigAction::ConsumerRelease,
             ],
@@ -233,7 +749,9 @@ impl Mapper {
         config.insert(
             "12".to_string(),
             vec![
-                ConfigAction::ConsumerPress { usage_id: 0xCD }, // Play/Pause
+                ConfigAction::ConsumerPress {
+                    usage_id: ConsumerUsage::Id(0xCD),
+                }, // Play/Pause
                 ConfigAction::Delay { ms: 10 },
                 ConfigAction::ConsumerRelease,
             ],
@@ -393,17 +911,21 @@ impl Mapper {
         );
 
         config
+            .into_iter()
+            .map(|(key, actions)| (key, ButtonMapping::Actions(actions)))
+            .collect()
     }
 
     /// Translates a configuration key string (e.g., "a", "LCtrl") and optional modifier string.
     /// Returns (modifier_bitmask, key_code).
     /// key_code will be 0 if the key_name itself represents a modifier.
-    fn translate_key(key_name: &str, mod_name: Option<&str>) -> (u8, u8) {
+    fn translate_key(&self, key_name: &str, mod_name: Option<&str>) -> (u8, u8) {
         log::debug!(
             "translate_key called: key_name=\"{}\", mod_name={:?}",
             key_name,
             mod_name
         );
+        let key_name = self.resolve_logical_key(key_name);
         // 1. Parse key string to KeyMappingCode
         let key_code_enum = match KeyMappingCode::from_str(key_name) {
             Ok(kc) => kc,
@@ -423,6 +945,7 @@ impl Mapper {
                 let mut combined_mask = 0u8;
                 for m_name in m_names.split_whitespace() {
                     // Split by whitespace
+                    let m_name = self.resolve_logical_key(m_name);
                     match KeyMappingCode::from_str(m_name) {
                         Ok(mod_code_enum) => {
                             let mod_map = KeyMap::from(mod_code_enum);
@@ -468,69 +991,949 @@ impl Mapper {
         (final_modifier_bitmask, key_code)
     }
 
-    /// Retrieves the sequence of primitive HidActions for a given button ID.
-    pub fn get_action_sequence(&self, button_id: i32) -> Vec<HidAction> {
-        let key = button_id.to_string();
+    // Maps logical key names to the key they stand for on the host OS. Without a
+    // host OS set, Primary is Ctrl, as on Linux and Windows.
+    fn resolve_logical_key<'a>(&self, key_name: &'a str) -> &'a str {
+        match key_name {
+            PRIMARY_KEY => match self.host_os {
+                Some(HostOs::MacOs) => "MetaLeft",
+                _ => "ControlLeft",
+            },
+            _ => key_name,
+        }
+    }
+
+    /// Checks every action of every button for names that would not translate, so that
+    /// typos are reported up front instead of becoming silent no-ops on the host.
+    pub fn validate(config: &MappingConfiguration, settings: &DeviceSettings) -> Vec<MappingError> {
+        let mut button_ids: Vec<&String> = config.keys().collect();
+        button_ids.sort();
+        let mut errors = Vec::new();
+        for button_id in button_ids {
+            // A SetHostOs earlier in the button's own actions counts as host_os being set
+            let mut settings = settings.clone();
+            for (name, actions) in config[button_id].action_lists() {
+                Self::validate_actions(button_id, name, actions, &mut settings, false, &mut errors);
+            }
+        }
+        errors
+    }
+
+    fn validate_actions(
+        button_id: &str,
+        path: &str,
+        actions: &[ConfigAction],
+        settings: &mut DeviceSettings,
+        in_repeat: bool,
+        errors: &mut Vec<MappingError>,
+    ) {
+        for (idx, action) in actions.iter().enumerate() {
+            let path = format!("{}[{}]", path, idx);
+            let mut report = |sub_path: String, kind: MappingErrorKind| {
+                errors.push(MappingError {
+                    page: None,
+                    button_id: button_id.to_string(),
+                    path: format!("{}.{}", path, sub_path),
+                    kind,
+                })
+            };
+            if let Some(name) = Self::applied_once(action).filter(|_| in_repeat) {
+                report(
+                    name.to_string(),
+                    MappingErrorKind::InRepeat(name.to_string()),
+                );
+            }
+            match action {
+                ConfigAction::KeyPress { keys, modifier } => {
+                    // Modifier keys go in the modifier byte, not in one of the 6 slots
+                    let slots = keys
+                        .iter()
+                        .filter(|key| Self::check_modifiers(key).is_some())
+                        .count();
+                    if slots > 6 && settings.key_rollover.unwrap_or_default() == KeyRollover::SixKey
+                    {
+                        report(
+                            "KeyPress.keys".to_string(),
+                            MappingErrorKind::TooManyKeys(slots),
+                        );
+                    }
+                    for (key_idx, key) in keys.iter().enumerate() {
+                        if let Some(kind) = Self::check_key(key) {
+                            report(format!("KeyPress.keys[{}]", key_idx), kind);
+                        }
+                    }
+                    if let Some(kind) = modifier.as_deref().and_then(Self::check_modifiers) {
+                        report("KeyPress.modifier".to_string(), kind);
+                    }
+                }
+                ConfigAction::SendString { keys, modifiers } => {
+                    if keys.len() != modifiers.len() {
+                        report(
+                            "SendString".to_string(),
+                            MappingErrorKind::MismatchedSendString {
+                                keys: keys.len(),
+                                modifiers: modifiers.len(),
+                            },
+                        );
+                    }
+                    for (key_idx, key) in keys.iter().enumerate() {
+                        if let Some(kind) = Self::check_key(key) {
+                            report(format!("SendString.keys[{}]", key_idx), kind);
+                        }
+                    }
+                    for (mod_idx, modifier) in modifiers.iter().enumerate() {
+                        if let Some(kind) = Self::check_modifiers(modifier) {
+                            report(format!("SendString.modifiers[{}]", mod_idx), kind);
+                        }
+                    }
+                }
+                ConfigAction::MouseMoveTo { x, y } => {
+                    for (name, value) in [("x", x), ("y", y)] {
+                        if !(0.0..=1.0).contains(value) {
+                            report(
+                                format!("MouseMoveTo.{}", name),
+                                MappingErrorKind::OutOfScreen(value.to_string()),
+                            );
+                        }
+                    }
+                }
+                ConfigAction::GamepadPress { button }
+                | ConfigAction::GamepadRelease {
+                    button: Some(button),
+                } if !(1..=GAMEPAD_BUTTONS).contains(button) => {
+                    let variant = match action {
+                        ConfigAction::GamepadPress { .. } => "GamepadPress",
+                        _ => "GamepadRelease",
+                    };
+                    report(
+                        format!("{}.button", variant),
+                        MappingErrorKind::InvalidGamepadButton(*button),
+                    );
+                }
+                ConfigAction::MidiNoteOn {
+                    channel,
+                    note,
+                    velocity,
+                } => Self::check_midi(
+                    "MidiNoteOn",
+                    *channel,
+                    &[("note", *note), ("velocity", *velocity)],
+                    &mut report,
+                ),
+                ConfigAction::MidiNoteOff { channel, note } => {
+                    Self::check_midi("MidiNoteOff", *channel, &[("note", *note)], &mut report)
+                }
+                ConfigAction::MidiCc {
+                    channel,
+                    controller,
+                    value,
+                } => Self::check_midi(
+                    "MidiCc",
+                    *channel,
+                    &[("controller", *controller), ("value", *value)],
+                    &mut report,
+                ),
+                ConfigAction::MidiProgramChange { channel, program } => Self::check_midi(
+                    "MidiProgramChange",
+                    *channel,
+                    &[("program", *program)],
+                    &mut report,
+                ),
+                ConfigAction::RawReport { bytes } if bytes.len() > RAW_REPORT_LEN => {
+                    report(
+                        "RawReport.bytes".to_string(),
+                        MappingErrorKind::RawReportTooLong(bytes.len()),
+                    );
+                }
+                ConfigAction::HttpRequest { url, .. }
+                    if !url.starts_with("http://") && !url.starts_with("https://") =>
+                {
+                    report(
+                        "HttpRequest.url".to_string(),
+                        MappingErrorKind::InvalidUrl(url.clone()),
+                    );
+                }
+                ConfigAction::TypeFromUrl {
+                    url,
+                    json_pointer,
+                    layout,
+                } => {
+                    if !url.starts_with("http://") && !url.starts_with("https://") {
+                        report(
+                            "TypeFromUrl.url".to_string(),
+                            MappingErrorKind::InvalidUrl(url.clone()),
+                        );
+                    }
+                    if let Some(pointer) = json_pointer.as_ref().filter(|p| !p.starts_with('/')) {
+                        report(
+                            "TypeFromUrl.json_pointer".to_string(),
+                            MappingErrorKind::InvalidJsonPointer(pointer.clone()),
+                        );
+                    }
+                    if let Err(e) = KeyboardLayout::from_name(layout.as_deref()) {
+                        report(
+                            "TypeFromUrl.layout".to_string(),
+                            MappingErrorKind::Text(e.to_string()),
+                        );
+                    }
+                }
+                ConfigAction::Osc { address, .. } if !address.starts_with('/') => {
+                    report(
+                        "Osc.address".to_string(),
+                        MappingErrorKind::InvalidOscAddress(address.clone()),
+                    );
+                }
+                ConfigAction::WakeOnLan { mac, .. } if wake_on_lan::parse_mac(mac).is_none() => {
+                    report(
+                        "WakeOnLan.mac".to_string(),
+                        MappingErrorKind::InvalidMacAddress(mac.clone()),
+                    );
+                }
+                ConfigAction::ConsumerPress {
+                    usage_id: ConsumerUsage::UnknownName(name),
+                } => {
+                    report(
+                        "ConsumerPress.usage_id".to_string(),
+                        MappingErrorKind::UnknownConsumerUsage(name.clone()),
+                    );
+                }
+                ConfigAction::SendUnicode { .. } if settings.host_os.is_none() => {
+                    report("SendUnicode".to_string(), MappingErrorKind::HostOsNotSet);
+                }
+                ConfigAction::SetHostOs { host_os } => settings.host_os = Some(*host_os),
+                ConfigAction::SendText { text, layout } => {
+                    if let Err(e) = KeyboardLayout::from_name(layout.as_deref())
+                        .and_then(|layout| layout.translate(text))
+                    {
+                        report(
+                            "SendText".to_string(),
+                            MappingErrorKind::Text(e.to_string()),
+                        );
+                    }
+                    if let Err(e) = text_template::check(text) {
+                        report(
+                            "SendText".to_string(),
+                            MappingErrorKind::Text(e.to_string()),
+                        );
+                    }
+                }
+                ConfigAction::Sequence(sub_sequence) => {
+                    Self::validate_actions(
+                        button_id,
+                        &format!("{}.Sequence", path),
+                        sub_sequence,
+                        settings,
+                        in_repeat,
+                        errors,
+                    );
+                }
+                ConfigAction::Repeat { actions, .. } => {
+                    Self::validate_actions(
+                        button_id,
+                        &format!("{}.Repeat.actions", path),
+                        actions,
+                        settings,
+                        true,
+                        errors,
+                    );
+                }
+                ConfigAction::RepeatWhileHeld {
+                    interval_ms,
+                    actions,
+                } => {
+                    if Duration::from_millis(*interval_ms) < HOLD_REPEAT_INTERVAL {
+                        report(
+                            "RepeatWhileHeld.interval_ms".to_string(),
+                            MappingErrorKind::IntervalTooShort {
+                                ms: *interval_ms,
+                                min: HOLD_REPEAT_INTERVAL.as_millis() as u64,
+                            },
+                        );
+                    }
+                    Self::validate_actions(
+                        button_id,
+                        &format!("{}.RepeatWhileHeld.actions", path),
+                        actions,
+                        settings,
+                        true,
+                        errors,
+                    );
+                }
+                ConfigAction::Toggle { on, off } => {
+                    Self::validate_actions(
+                        button_id,
+                        &format!("{}.Toggle.on", path),
+                        on,
+                        settings,
+                        in_repeat,
+                        errors,
+                    );
+                    Self::validate_actions(
+                        button_id,
+                        &format!("{}.Toggle.off", path),
+                        off,
+                        settings,
+                        in_repeat,
+                        errors,
+                    );
+                }
+                ConfigAction::IfLed { on, off, .. } => {
+                    Self::validate_actions(
+                        button_id,
+                        &format!("{}.IfLed.on", path),
+                        on,
+                        settings,
+                        in_repeat,
+                        errors,
+                    );
+                    Self::validate_actions(
+                        button_id,
+                        &format!("{}.IfLed.off", path),
+                        off,
+                        settings,
+                        in_repeat,
+                        errors,
+                    );
+                }
+                _ => {}
+            }
+        }
+    }
+
+    // Actions that take effect while the sequence is translated rather than as the macro
+    // runs, so a loop around them would only apply them once
+    fn applied_once(action: &ConfigAction) -> Option<&'static str> {
+        match action {
+            ConfigAction::Toggle { .. } => Some("Toggle"),
+            ConfigAction::IfLed { .. } => Some("IfLed"),
+            ConfigAction::SetHostOs { .. } => Some("SetHostOs"),
+            ConfigAction::SwitchPage { .. } => Some("SwitchPage"),
+            ConfigAction::PushPage { .. } => Some("PushPage"),
+            ConfigAction::PopPage => Some("PopPage"),
+            ConfigAction::SendText { text, .. } if text_template::has_counter(text) => {
+                Some("SendText")
+            }
+            _ => None,
+        }
+    }
+
+    // MIDI channels are 1 to 16 and data bytes are 7 bits
+    fn check_midi(
+        variant: &str,
+        channel: u8,
+        data: &[(&str, u8)],
+        report: &mut impl FnMut(String, MappingErrorKind),
+    ) {
+        if !(1..=16).contains(&channel) {
+            report(
+                format!("{}.channel", variant),
+                MappingErrorKind::OutOfRange {
+                    value: channel,
+                    min: 1,
+                    max: 16,
+                },
+            );
+        }
+        for (name, value) in data {
+            if *value > 127 {
+                report(
+                    format!("{}.{}", variant, name),
+                    MappingErrorKind::OutOfRange {
+                        value: *value,
+                        min: 0,
+                        max: 127,
+                    },
+                );
+            }
+        }
+    }
+
+    fn expand_template(&mut self, text: &str) -> Result<String, TemplateError> {
+        self.template_values.expand(text, SystemTime::now().into())
+    }
+
+    fn check_key(key_name: &str) -> Option<MappingErrorKind> {
+        (key_name != PRIMARY_KEY && KeyMappingCode::from_str(key_name).is_err())
+            .then(|| MappingErrorKind::InvalidKey(key_name.to_string()))
+    }
+
+    // Modifiers are space separated, and each one must be a modifier key
+    fn check_modifiers(mod_names: &str) -> Option<MappingErrorKind> {
+        mod_names
+            .split_whitespace()
+            .find(|m_name| {
+                *m_name != PRIMARY_KEY
+                    && !matches!(
+                        KeyMappingCode::from_str(m_name).map(|code| KeyMap::from(code).usb),
+                        Ok(0xE0..=0xE7)
+                    )
+            })
+            .map(|m_name| MappingErrorKind::InvalidModifier(m_name.to_string()))
+    }
+
+    /// Returns the gestures that have actions bound for a given button ID.
+    pub fn get_gesture_bindings(&self, page: usize, button_id: i32) -> GestureBindings {
+        self.mapping(page, button_id)
+            .map(ButtonMapping::gesture_bindings)
+            .unwrap_or_default()
+    }
+
+    /// Retrieves the macro for a button on a given page and press phase.
+    /// Page navigation actions take effect immediately, while the sequence is being translated.
+    pub fn get_action_sequence(
+        &mut self,
+        page: usize,
+        button_id: i32,
+        phase: ButtonPhase,
+    ) -> Vec<MacroOp> {
         let config_sequence = self
-            .config
-            .get(&key)
-            .or_else(|| self.config.get("default")) // Fallback to default if specific ID not found
-            .cloned() // Clone the sequence to avoid borrowing issues
+            .mapping(page, button_id)
+            .map(|mapping| mapping.actions_for(phase).to_vec()) // Clone the sequence to avoid borrowing issues
             .unwrap_or_default(); // Return empty sequence if neither found
 
-        Self::translate_sequence(config_sequence)
+        self.translate_sequence(page, button_id, config_sequence)
     }
 
-    /// Recursively translates a sequence of ConfigActions into HidActions.
-    fn translate_sequence(config_actions: Vec<ConfigAction>) -> Vec<HidAction> {
-        let mut hid_actions = Vec::new();
+    /// Recursively translates a sequence of ConfigActions into macro ops. Loops are kept
+    /// as loops, to be expanded by a MacroCursor as the macro runs.
+    fn translate_sequence(
+        &mut self,
+        page: usize,
+        button_id: i32,
+        config_actions: Vec<ConfigAction>,
+    ) -> Vec<MacroOp> {
+        let mut ops = Vec::new();
         for action in config_actions {
             match action {
                 ConfigAction::KeyPress { keys, modifier } => {
-                    let mut keycodes = [0u8; 6];
+                    let mut keycodes = Vec::with_capacity(keys.len());
                     let mut mod_bits = 0u8;
-                    for (idx, key) in keys.iter().take(6).enumerate() {
-                        let (mb, key_code) = Self::translate_key(key, modifier.as_deref());
-                        keycodes[idx] = key_code;
+                    for key in keys.iter() {
+                        let (mb, key_code) = self.translate_key(key, modifier.as_deref());
+                        if key_code != 0 {
+                            keycodes.push(key_code);
+                        }
                         mod_bits |= mb;
                     }
-                    hid_actions.push(HidAction::KeyPress(mod_bits, keycodes));
+                    ops.push(HidAction::KeyPress(mod_bits, keycodes).into());
                 }
-                ConfigAction::KeyRelease => hid_actions.push(HidAction::KeyRelease),
-                ConfigAction::MouseMove { dx, dy } => {
-                    hid_actions.push(HidAction::MouseMove(dx, dy))
+                ConfigAction::KeyRelease => ops.push(HidAction::KeyRelease.into()),
+                ConfigAction::MouseMove { dx, dy } => ops.push(HidAction::MouseMove(dx, dy).into()),
+                ConfigAction::MouseGlide {
+                    dx,
+                    dy,
+                    duration_ms,
+                } => ops.push(MacroOp::Glide {
+                    dx,
+                    dy,
+                    duration: Duration::from_millis(duration_ms),
+                }),
+                ConfigAction::MouseMoveTo { x, y } => {
+                    let to_absolute =
+                        |v: f32| (v.clamp(0.0, 1.0) * ABSOLUTE_MOUSE_MAX as f32).round() as u16;
+                    ops.push(HidAction::MouseMoveTo(to_absolute(x), to_absolute(y)).into())
                 }
                 ConfigAction::MousePress { button } => {
-                    hid_actions.push(HidAction::MousePress(button))
+                    ops.push(HidAction::MousePress(button).into())
                 }
-                ConfigAction::MouseRelease => hid_actions.push(HidAction::MouseRelease),
+                ConfigAction::MouseRelease => ops.push(HidAction::MouseRelease.into()),
                 ConfigAction::MouseWheel { amount } => {
-                    hid_actions.push(HidAction::MouseWheel(amount))
+                    ops.push(HidAction::MouseWheel(amount).into())
+                }
+                ConfigAction::MouseHScroll { amount } => {
+                    ops.push(HidAction::MouseHScroll(amount).into())
+                }
+                ConfigAction::Drag { button, path } => {
+                    ops.push(HidAction::MousePress(button).into());
+                    for point in path {
+                        ops.push(MacroOp::Glide {
+                            dx: point.dx,
+                            dy: point.dy,
+                            duration: Duration::from_millis(point.duration_ms),
+                        });
+                    }
+                    ops.push(HidAction::MouseRelease.into());
+                }
+                ConfigAction::ConsumerPress { usage_id } => match usage_id.id() {
+                    Some(id) => ops.push(HidAction::ConsumerPress(id).into()),
+                    None => log::error!(
+                        "Not pressing ConsumerPress on button {}: unknown usage {:?}",
+                        button_id,
+                        usage_id
+                    ),
+                },
+                ConfigAction::ConsumerRelease => ops.push(HidAction::ConsumerRelease.into()),
+                ConfigAction::SystemPress { control } => {
+                    ops.push(HidAction::SystemPress(control as u8).into())
+                }
+                ConfigAction::SystemRelease => ops.push(HidAction::SystemRelease.into()),
+                ConfigAction::GamepadPress { button }
+                | ConfigAction::GamepadRelease {
+                    button: Some(button),
+                } if !(1..=GAMEPAD_BUTTONS).contains(&button) => {
+                    log::error!("Ignoring gamepad button {} on button {}", button, button_id);
+                }
+                ConfigAction::GamepadPress { button } => {
+                    ops.push(HidAction::GamepadPress(button).into())
+                }
+                ConfigAction::GamepadRelease { button } => {
+                    ops.push(HidAction::GamepadRelease(button).into())
                 }
-                ConfigAction::ConsumerPress { usage_id } => {
-                    hid_actions.push(HidAction::ConsumerPress(usage_id))
+                ConfigAction::GamepadAxis { axis, value } => {
+                    // -128 is outside the axis' logical range
+                    ops.push(HidAction::GamepadAxis(axis as u8, value.max(-127)).into())
                 }
-                ConfigAction::ConsumerRelease => hid_actions.push(HidAction::ConsumerRelease),
+                ConfigAction::GamepadHat { direction } => {
+                    ops.push(HidAction::GamepadHat(direction as u8).into())
+                }
+                ConfigAction::MidiNoteOn {
+                    channel,
+                    note,
+                    velocity,
+                } => ops.push(
+                    HidAction::Midi(MidiMessage::NoteOn {
+                        channel: channel.saturating_sub(1),
+                        note,
+                        velocity,
+                    })
+                    .into(),
+                ),
+                ConfigAction::MidiNoteOff { channel, note } => ops.push(
+                    HidAction::Midi(MidiMessage::NoteOff {
+                        channel: channel.saturating_sub(1),
+                        note,
+                        velocity: 0,
+                    })
+                    .into(),
+                ),
+                ConfigAction::MidiCc {
+                    channel,
+                    controller,
+                    value,
+                } => ops.push(
+                    HidAction::Midi(MidiMessage::ControlChange {
+                        channel: channel.saturating_sub(1),
+                        controller,
+                        value,
+                    })
+                    .into(),
+                ),
+                ConfigAction::MidiProgramChange { channel, program } => ops.push(
+                    HidAction::Midi(MidiMessage::ProgramChange {
+                        channel: channel.saturating_sub(1),
+                        program,
+                    })
+                    .into(),
+                ),
+                ConfigAction::RawReport { bytes } => ops.push(HidAction::RawReport(bytes).into()),
+                ConfigAction::HttpRequest {
+                    method,
+                    url,
+                    headers,
+                    body,
+                } => ops.push(
+                    HidAction::HttpRequest(WebRequest {
+                        method,
+                        url,
+                        headers: headers.into_iter().collect(),
+                        body,
+                    })
+                    .into(),
+                ),
+                ConfigAction::Osc {
+                    host,
+                    port,
+                    address,
+                    args,
+                } => ops
+                    .push(HidAction::Udp(host, port, osc::encode_message(&address, &args)).into()),
+                ConfigAction::UdpSend {
+                    host,
+                    port,
+                    payload,
+                } => ops.push(HidAction::Udp(host, port, payload.into_bytes()).into()),
+                ConfigAction::TypeFromUrl {
+                    url,
+                    json_pointer,
+                    layout,
+                } => ops.push(
+                    HidAction::TypeFromUrl(UrlText {
+                        url,
+                        json_pointer,
+                        layout,
+                    })
+                    .into(),
+                ),
+                ConfigAction::WakeOnLan { mac, broadcast } => match wake_on_lan::parse_mac(&mac) {
+                    Some(mac) => ops.push(
+                        HidAction::Udp(
+                            broadcast.unwrap_or_else(|| wake_on_lan::DEFAULT_BROADCAST.to_string()),
+                            wake_on_lan::WAKE_ON_LAN_PORT,
+                            wake_on_lan::magic_packet(mac),
+                        )
+                        .into(),
+                    ),
+                    None => log::error!("Invalid MAC address {} on button {}", mac, button_id),
+                },
                 ConfigAction::Delay { ms } => {
-                    hid_actions.push(HidAction::Delay(Duration::from_millis(ms)))
+                    ops.push(HidAction::Delay(Duration::from_millis(ms)).into())
                 }
                 ConfigAction::SendString { keys, modifiers } => {
-                    for (key, modifier) in keys.iter().zip(modifiers.iter()) {
-                        let modifier = if !modifier.is_empty() {
-                            Some(modifier.as_str())
-                        } else {
-                            None
-                        };
-                        let (mb, key_code) = Self::translate_key(key, modifier);
-                        hid_actions.push(HidAction::KeyPress(mb, [key_code, 0, 0, 0, 0, 0]));
-                        hid_actions.push(HidAction::KeyRelease);
+                    let key_strokes: Vec<KeyStroke> = keys
+                        .iter()
+                        .zip(modifiers.iter())
+                        .map(|(key, modifier)| {
+                            let modifier = if !modifier.is_empty() {
+                                Some(modifier.as_str())
+                            } else {
+                                None
+                            };
+                            let (mb, key_code) = self.translate_key(key, modifier);
+                            KeyStroke {
+                                modifier: mb,
+                                keycode: key_code,
+                            }
+                        })
+                        .collect();
+                    ops.extend(typing_sequence(key_strokes));
+                }
+                ConfigAction::SendText { text, layout } => {
+                    let key_strokes = match self.expand_template(&text) {
+                        Ok(text) => KeyboardLayout::from_name(layout.as_deref())
+                            .and_then(|layout| layout.translate(&text))
+                            .map_err(|e| e.to_string()),
+                        Err(e) => Err(e.to_string()),
+                    };
+                    match key_strokes {
+                        Ok(key_strokes) => ops.extend(typing_sequence(key_strokes)),
+                        Err(e) => {
+                            log::error!("Not typing SendText on button {}: {}", button_id, e);
+                        }
                     }
                 }
+                ConfigAction::SendUnicode { text } => match self.host_os {
+                    Some(host_os) => {
+                        for c in text.chars() {
+                            ops.extend(unicode_sequence(c, host_os).into_iter().map(MacroOp::from));
+                        }
+                    }
+                    None => {
+                        log::error!(
+                            "Not typing SendUnicode on button {}: host_os is not set in settings",
+                            button_id
+                        );
+                    }
+                },
                 ConfigAction::Sequence(sub_sequence) => {
-                    hid_actions.extend(Self::translate_sequence(sub_sequence));
+                    ops.extend(self.translate_sequence(page, button_id, sub_sequence));
+                }
+                ConfigAction::StopAll => ops.push(HidAction::StopMacros(None).into()),
+                ConfigAction::StopButton { button_id } => {
+                    ops.push(HidAction::StopMacros(Some(button_id)).into())
+                }
+                ConfigAction::Repeat { count, actions } => {
+                    let sub_ops = self.translate_sequence(page, button_id, actions);
+                    ops.push(MacroOp::Repeat {
+                        count,
+                        ops: sub_ops.into(),
+                    });
+                }
+                ConfigAction::RepeatWhileHeld {
+                    interval_ms,
+                    actions,
+                } => {
+                    let sub_ops = self.translate_sequence(page, button_id, actions);
+                    ops.push(MacroOp::RepeatWhileHeld {
+                        interval: Duration::from_millis(interval_ms),
+                        ops: sub_ops.into(),
+                    });
                 }
+                ConfigAction::Toggle { on, off } => {
+                    let latched = self.toggle_states.entry((page, button_id)).or_insert(false);
+                    *latched = !*latched;
+                    let sub_sequence = if *latched { on } else { off };
+                    ops.extend(self.translate_sequence(page, button_id, sub_sequence));
+                }
+                ConfigAction::IfLed { led, on, off } => {
+                    let sub_sequence = if self.keyboard_leds.is_lit(led as u8) {
+                        on
+                    } else {
+                        off
+                    };
+                    ops.extend(self.translate_sequence(page, button_id, sub_sequence));
+                }
+                ConfigAction::SetHostOs { host_os } => self.host_os = Some(host_os),
+                ConfigAction::SwitchPage { page: name } => {
+                    if let Some(index) = self.page_index(&name) {
+                        self.current_page = index;
+                    }
+                }
+                ConfigAction::PushPage { page: name } => {
+                    if let Some(index) = self.page_index(&name) {
+                        self.page_stack.push(self.current_page);
+                        self.current_page = index;
+                    }
+                }
+                ConfigAction::PopPage => match self.page_stack.pop() {
+                    Some(index) => self.current_page = index,
+                    None => log::warn!("PopPage with no page to go back to"),
+                },
             }
         }
-        hid_actions
+        ops
+    }
+}
+
+/// Presses and releases each key stroke in turn.
+pub fn typing_sequence(key_strokes: Vec<KeyStroke>) -> Vec<MacroOp> {
+    key_strokes
+        .into_iter()
+        .flat_map(|stroke| {
+            [
+                HidAction::KeyPress(stroke.modifier, vec![stroke.keycode]).into(),
+                HidAction::KeyRelease.into(),
+            ]
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn mappings(json: &str) -> MappingConfiguration {
+        serde_json::from_str(json).unwrap()
+    }
+
+    fn mapper(pages: &[(&str, &str)]) -> Mapper {
+        Mapper::new(
+            pages
+                .iter()
+                .map(|(name, json)| PageConfig {
+                    name: name.to_string(),
+                    mappings: mappings(json),
+                    button_names: None,
+                })
+                .collect(),
+        )
+    }
+
+    fn press(mapper: &mut Mapper, button_id: i32) -> Vec<MacroOp> {
+        mapper.get_action_sequence(mapper.current_page(), button_id, ButtonPhase::Press)
+    }
+
+    fn key_press(modifier: u8, keycode: u8) -> MacroOp {
+        HidAction::KeyPress(modifier, vec![keycode]).into()
+    }
+
+    #[test]
+    fn valid_mapping_has_no_errors() {
+        let config = mappings(
+            r#"{"1": [{"KeyPress": {"keys": ["KeyA"], "modifier": "ShiftLeft"}}, "KeyRelease"],
+                "2": {"on_press": [{"Repeat": {"count": 3, "actions": [{"MouseWheel": {"amount": 1}}]}}]}}"#,
+        );
+        assert_eq!(
+            Mapper::validate(&config, &DeviceSettings::default()),
+            vec![]
+        );
+    }
+
+    #[test]
+    fn errors_point_at_the_action() {
+        let config = mappings(
+            r#"{"1": {"on_press": [{"Delay": {"ms": 1}}, {"Sequence": [
+                      {"KeyPress": {"keys": ["KeyA", "NoSuchKey"], "modifier": "KeyB"}}]}]},
+                "2": [{"MouseMoveTo": {"x": 1.5, "y": 0.5}}]}"#,
+        );
+        let errors = Mapper::validate(&config, &DeviceSettings::default());
+        let found: Vec<(&str, &str, &MappingErrorKind)> = errors
+            .iter()
+            .map(|e| (e.button_id.as_str(), e.path.as_str(), &e.kind))
+            .collect();
+        assert_eq!(
+            found,
+            vec![
+                (
+                    "1",
+                    "on_press[1].Sequence[0].KeyPress.keys[1]",
+                    &MappingErrorKind::InvalidKey("NoSuchKey".to_string())
+                ),
+                (
+                    "1",
+                    "on_press[1].Sequence[0].KeyPress.modifier",
+                    &MappingErrorKind::InvalidModifier("KeyB".to_string())
+                ),
+                (
+                    "2",
+                    "[0].MouseMoveTo.x",
+                    &MappingErrorKind::OutOfScreen("1.5".to_string())
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn large_chords_need_nkro() {
+        let config = mappings(
+            r#"{"1": [{"KeyPress": {"keys": ["KeyA", "KeyB", "KeyC", "KeyD", "KeyE", "KeyF", "KeyG", "ShiftLeft"]}}]}"#,
+        );
+        let errors = Mapper::validate(&config, &DeviceSettings::default());
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].path, "[0].KeyPress.keys");
+        assert_eq!(errors[0].kind, MappingErrorKind::TooManyKeys(7));
+
+        let settings = DeviceSettings {
+            key_rollover: Some(KeyRollover::NKey),
+            ..Default::default()
+        };
+        assert_eq!(Mapper::validate(&config, &settings), vec![]);
+    }
+
+    #[test]
+    fn send_unicode_needs_a_host_os() {
+        let config = mappings(r#"{"1": [{"SendUnicode": {"text": "é"}}]}"#);
+        let errors = Mapper::validate(&config, &DeviceSettings::default());
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].kind, MappingErrorKind::HostOsNotSet);
+
+        let settings = DeviceSettings {
+            host_os: Some(HostOs::Linux),
+            ..Default::default()
+        };
+        assert_eq!(Mapper::validate(&config, &settings), vec![]);
+
+        // Only a SetHostOs on the same button, before the SendUnicode, counts
+        let config = mappings(
+            r#"{"1": [{"SetHostOs": {"host_os": "Windows"}}, {"SendUnicode": {"text": "é"}}],
+                "2": [{"SendUnicode": {"text": "é"}}]}"#,
+        );
+        let errors = Mapper::validate(&config, &DeviceSettings::default());
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].button_id, "2");
+    }
+
+    #[test]
+    fn actions_applied_once_are_rejected_in_repeats() {
+        let config = mappings(
+            r#"{"1": [{"Repeat": {"count": 2, "actions": [
+                      {"Sequence": [{"SwitchPage": {"page": "Main"}}]},
+                      {"Toggle": {"on": [], "off": []}}]}}],
+                "2": [{"RepeatWhileHeld": {"interval_ms": 100, "actions": ["PopPage"]}}],
+                "3": [{"Toggle": {"on": ["PopPage"], "off": []}}]}"#,
+        );
+        let errors = Mapper::validate(&config, &DeviceSettings::default());
+        let found: Vec<(&str, &str)> = errors
+            .iter()
+            .map(|e| (e.button_id.as_str(), e.path.as_str()))
+            .collect();
+        assert_eq!(
+            found,
+            vec![
+                ("1", "[0].Repeat.actions[0].Sequence[0].SwitchPage"),
+                ("1", "[0].Repeat.actions[1].Toggle"),
+                ("2", "[0].RepeatWhileHeld.actions[0].PopPage"),
+            ]
+        );
+        assert_eq!(
+            errors[1].kind,
+            MappingErrorKind::InRepeat("Toggle".to_string())
+        );
+    }
+
+    #[test]
+    fn repeat_while_held_interval_has_a_minimum() {
+        let config = mappings(
+            r#"{"1": [{"RepeatWhileHeld": {"interval_ms": 0, "actions": [{"MouseWheel": {"amount": 1}}]}}]}"#,
+        );
+        let errors = Mapper::validate(&config, &DeviceSettings::default());
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].path, "[0].RepeatWhileHeld.interval_ms");
+        assert_eq!(
+            errors[0].kind,
+            MappingErrorKind::IntervalTooShort { ms: 0, min: 50 }
+        );
+    }
+
+    #[test]
+    fn toggle_alternates_and_latches() {
+        let mut mapper = mapper(&[(
+            "Main",
+            r#"{"1": [{"Toggle": {"on": [{"MouseWheel": {"amount": 1}}], "off": [{"MouseWheel": {"amount": -1}}]}}]}"#,
+        )]);
+        assert_eq!(press(&mut mapper, 1), vec![HidAction::MouseWheel(1).into()]);
+        assert!(mapper.is_latched(0, 1));
+        assert_eq!(mapper.latched_buttons(0), vec![1]);
+        assert_eq!(
+            press(&mut mapper, 1),
+            vec![HidAction::MouseWheel(-1).into()]
+        );
+        assert!(!mapper.is_latched(0, 1));
+    }
+
+    #[test]
+    fn if_led_follows_the_host_leds() {
+        let mut mapper = mapper(&[(
+            "Main",
+            r#"{"1": [{"IfLed": {"led": "CapsLock", "on": [{"MouseWheel": {"amount": 1}}]}}]}"#,
+        )]);
+        assert_eq!(press(&mut mapper, 1), vec![]);
+        mapper.set_keyboard_leds(KeyboardLeds(0b10));
+        assert_eq!(press(&mut mapper, 1), vec![HidAction::MouseWheel(1).into()]);
+    }
+
+    #[test]
+    fn page_actions_move_between_pages() {
+        let mut mapper = mapper(&[
+            ("Main", r#"{"1": [{"PushPage": {"page": "Media"}}]}"#),
+            (
+                "Media",
+                r#"{"1": ["PopPage"], "2": [{"SwitchPage": {"page": "Apps"}}]}"#,
+            ),
+            ("Apps", r#"{"1": [{"SwitchPage": {"page": "Missing"}}]}"#),
+        ]);
+        press(&mut mapper, 1);
+        assert_eq!(mapper.current_page(), 1);
+        press(&mut mapper, 1);
+        assert_eq!(mapper.current_page(), 0);
+        press(&mut mapper, 1);
+        press(&mut mapper, 2);
+        assert_eq!(mapper.current_page(), 2);
+        // Unknown pages leave the current one shown
+        press(&mut mapper, 1);
+        assert_eq!(mapper.current_page(), 2);
+    }
+
+    #[test]
+    fn primary_depends_on_the_host_os() {
+        let mut mapper = mapper(&[(
+            "Main",
+            r#"{"1": [{"KeyPress": {"keys": ["KeyC"], "modifier": "Primary"}}],
+                "2": [{"KeyPress": {"keys": ["Primary"]}}]}"#,
+        )]);
+        // Ctrl without a host OS
+        assert_eq!(press(&mut mapper, 1), vec![key_press(0x01, 0x06)]);
+        mapper.set_host_os(Some(HostOs::Windows));
+        assert_eq!(press(&mut mapper, 1), vec![key_press(0x01, 0x06)]);
+        mapper.set_host_os(Some(HostOs::MacOs));
+        assert_eq!(press(&mut mapper, 1), vec![key_press(0x08, 0x06)]);
+        assert_eq!(
+            press(&mut mapper, 2),
+            vec![HidAction::KeyPress(0x08, vec![]).into()]
+        );
+    }
+
+    #[test]
+    fn mouse_move_to_scales_to_the_absolute_range() {
+        let mut mapper = mapper(&[(
+            "Main",
+            r#"{"1": [{"MouseMoveTo": {"x": 0.5, "y": 1.0}}, {"MouseMoveTo": {"x": -1.0, "y": 2.0}}]}"#,
+        )]);
+        assert_eq!(
+            press(&mut mapper, 1),
+            vec![
+                HidAction::MouseMoveTo(16384, ABSOLUTE_MOUSE_MAX).into(),
+                HidAction::MouseMoveTo(0, ABSOLUTE_MOUSE_MAX).into(),
+            ]
+        );
     }
 }
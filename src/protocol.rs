@@ -1,8 +1,9 @@
 use std::sync::mpsc::{Receiver, Sender, SyncSender};
 
 use crate::bsp::usb::{send_usb_message, UsbMessageError};
-use crate::config::{ConfigUpdatedFor, Configurator, DeviceConfig, WifiSettings};
+use crate::config::{ConfigUpdatedFor, Configurator, DeviceConfig, InvalidMappings, WifiSettings};
 use crate::events::AppEvent;
+use crate::mapper::MappingError;
 use serde::{Deserialize, Serialize};
 
 //Major version: 1, Minor version: 0
@@ -68,6 +69,9 @@ pub struct ErrorResponse {
     pub message: String,
     #[serde(rename = "errorCode")]
     pub error_code: u32,
+    // Every problem found when a SetConfig is rejected for invalid mappings
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub errors: Vec<MappingError>,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, Default)]
@@ -139,6 +143,7 @@ impl<'a> ProtocolManager<'a> {
                                 },
                                 message: format!("Failed to get config: {}", e),
                                 error_code: 2,
+                                errors: Vec::new(),
                             };
                             let response_message = serde_json::to_vec(&error_response)
                                 .unwrap_or_else(|_| b"{}".to_vec());
@@ -155,6 +160,7 @@ impl<'a> ProtocolManager<'a> {
                             header: response.header,
                             message: format!("Failed to serialize response: {}", e),
                             error_code: 3,
+                            errors: Vec::new(),
                         };
                         serde_json::to_vec(&error_response).unwrap_or_else(|_| b"{}".to_vec())
                     }
@@ -171,12 +177,10 @@ impl<'a> ProtocolManager<'a> {
                 let mut config_updated_for = ConfigUpdatedFor::default();
                 let response = match self.config.save(&new_config, &mut config_updated_for) {
                     Ok(_) => {
-                        if self
-                            .actor_tx
-                            .send(AppEvent::MappingUpdated(new_config.mappings))
-                            .is_err()
-                        {
-                            log::error!("Error sending mapping updated event. Will need to reboot for updated mappings to take effect");
+                        if let Some(pages) = self.config.get_pages() {
+                            if self.actor_tx.send(AppEvent::MappingUpdated(pages)).is_err() {
+                                log::error!("Error sending mapping updated event. Will need to reboot for updated mappings to take effect");
+                            }
                         }
                         let response = AckResponse {
                             header: response_header,
@@ -191,6 +195,7 @@ impl<'a> ProtocolManager<'a> {
                                     header: response.header,
                                     message: format!("Failed to serialize response: {}", e),
                                     error_code: 3,
+                                    errors: Vec::new(),
                                 };
                                 serde_json::to_vec(&error_response)
                                     .unwrap_or_else(|_| b"{}".to_vec())
@@ -199,10 +204,16 @@ impl<'a> ProtocolManager<'a> {
                     }
                     Err(e) => {
                         log::error!("Error saving config: {}", e);
+                        // Every invalid action is reported, so they can all be fixed at once
+                        let (error_code, errors) = match e.downcast_ref::<InvalidMappings>() {
+                            Some(InvalidMappings(errors)) => (4, errors.clone()),
+                            None => (1, Vec::new()),
+                        };
                         let response = ErrorResponse {
                             header: response_header,
                             message: e.to_string(),
-                            error_code: 1,
+                            error_code,
+                            errors,
                         };
                         match serde_json::to_vec(&response) {
                             Ok(msg) => msg,
@@ -214,6 +225,22 @@ impl<'a> ProtocolManager<'a> {
                     }
                 };
                 send_response(response);
+                if config_updated_for.gesture_timing
+                    || config_updated_for.host_os
+                    || config_updated_for.key_rollover
+                    || config_updated_for.timezone_offset
+                    || config_updated_for.device_name
+                {
+                    if let Some(settings) = self.config.get_settings() {
+                        if self
+                            .actor_tx
+                            .send(AppEvent::SettingsUpdated(settings))
+                            .is_err()
+                        {
+                            log::error!("Error sending settings updated event. Will need to reboot for updated settings to take effect");
+                        }
+                    }
+                }
                 if config_updated_for.wifi {
                     if let Err(e) = self
                         .main_wifi_time_init_tx
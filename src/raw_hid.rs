@@ -0,0 +1,33 @@
+use crate::events::AppEvent;
+use crate::http_handlers::UserStatus;
+
+// Commands a host application can send in the first byte of a raw output report
+const COMMAND_SET_BUTTON_LABEL: u8 = 0x01;
+const COMMAND_SET_STATUS: u8 = 0x02;
+
+/// Turns a raw output report from the host into the event it asks for.
+///
+/// - `0x01, button_id, label...` relabels a button (IDs start at 1) until the page changes.
+/// - `0x02, text...` shows the text as the user status.
+///
+/// Text is UTF-8 and ends at the first zero byte or at the end of the report.
+pub fn parse_output_report(report: &[u8]) -> Option<AppEvent> {
+    match report {
+        [COMMAND_SET_BUTTON_LABEL, button_id, label @ ..] if *button_id > 0 => Some(
+            AppEvent::ButtonLabelUpdate(*button_id as i32, text_from(label)),
+        ),
+        [COMMAND_SET_STATUS, text @ ..] => Some(AppEvent::UserStatusUpdate(UserStatus {
+            text: text_from(text),
+            bgcolor: None,
+        })),
+        _ => {
+            log::warn!("Ignoring unknown raw output report: {:02X?}", report);
+            None
+        }
+    }
+}
+
+fn text_from(bytes: &[u8]) -> String {
+    let end = bytes.iter().position(|b| *b == 0).unwrap_or(bytes.len());
+    String::from_utf8_lossy(&bytes[..end]).into_owned()
+}
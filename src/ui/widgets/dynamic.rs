@@ -2,6 +2,7 @@ use std::{collections::HashMap, rc::Rc, sync::Arc, time::Duration};
 
 use crate::{
     config::{WidgetItemConfig, WidgetKindConfig},
+    fetched_text::json_value_at,
     http_client::HttpClientPool,
     ui::window::{MainWindow, WidgetItem, WidgetKind},
 };
@@ -117,8 +118,7 @@ fn fetch_and_process_text(
     let text = pool.get(url)?;
 
     let processed_text = if let Some(path) = path {
-        let json = serde_json::from_str::<serde_json::Value>(&text)?;
-        if let Some(value) = json.pointer(path) {
+        if let Some(value) = json_value_at(&text, path)? {
             value.to_string()
         } else {
             log::info!("No value found at path: {}", path);
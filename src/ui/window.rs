@@ -1,7 +1,7 @@
 use anyhow::Result;
 use chrono::{DateTime, FixedOffset, Utc};
 use esp_idf_svc::hal::i2c::I2cDriver;
-use slint::{Color, SharedString, Weak};
+use slint::{Color, Model, SharedString, Weak};
 use std::{
     collections::HashMap,
     sync::{
@@ -13,7 +13,7 @@ use std::{
 
 use crate::{
     bsp::slint_platform,
-    config::WidgetItemConfig,
+    config::{PageConfig, WidgetItemConfig},
     events::{AppEvent, TimeStatus, UsbStatus, WifiStatus},
     http_client::HttpClientPool,
 };
@@ -29,7 +29,7 @@ impl Window {
         rx: Receiver<AppEvent>,
         actor_tx: Sender<AppEvent>,
         tz_offset: f32,
-        button_names: Option<HashMap<usize, String>>,
+        pages: Vec<PageConfig>,
         http_pool: Arc<HttpClientPool>,
         widgets: Option<HashMap<usize, WidgetItemConfig>>,
     ) -> Result<()> {
@@ -37,17 +37,27 @@ impl Window {
         let window = MainWindow::new()
             .map_err(|e| anyhow::anyhow!("Failed to create main window: {}", e))?;
 
-        set_button_names(&window, button_names);
+        set_page_names(&window, &pages);
+        set_button_names(
+            &window,
+            pages.first().and_then(|page| page.button_names.clone()),
+        );
         install_test_callback(&window);
 
         let weak_window_status_updates = window.as_weak();
         let timer_status_updates = slint::Timer::default();
         let mut status_list_items: Vec<SharedString> = Vec::new();
+        let mut pages = pages;
         timer_status_updates.start(
             slint::TimerMode::Repeated,
             Duration::from_millis(500),
             move || {
-                handle_events(&weak_window_status_updates, &rx, &mut status_list_items);
+                handle_events(
+                    &weak_window_status_updates,
+                    &rx,
+                    &mut pages,
+                    &mut status_list_items,
+                );
             },
         );
 
@@ -65,6 +75,18 @@ impl Window {
             let _ = button_actor_tx.send(AppEvent::ButtonPressed(button_id));
         });
 
+        let button_actor_tx = actor_tx.clone();
+        window.on_button_released(move |button_id: i32| {
+            log::info!("Button {} released in UI! Sending to Actor.", button_id);
+            let _ = button_actor_tx.send(AppEvent::ButtonReleased(button_id));
+        });
+
+        let page_actor_tx = actor_tx.clone();
+        window.on_page_selected(move |page: i32| {
+            log::info!("Page {} selected in UI! Sending to Actor.", page);
+            let _ = page_actor_tx.send(AppEvent::PageSelected(page as usize));
+        });
+
         super::widgets::dynamic::start_widget_service(window.as_weak(), http_pool.clone(), widgets);
         super::widgets::server::start_server_widget_service(window.as_weak());
 
@@ -104,6 +126,14 @@ pub fn set_button_names(window: &MainWindow, button_names: Option<HashMap<usize,
     window.set_button_names(names.as_slice().into());
 }
 
+pub fn set_page_names(window: &MainWindow, pages: &[PageConfig]) {
+    let names: Vec<SharedString> = pages
+        .iter()
+        .map(|page| SharedString::from(&page.name))
+        .collect();
+    window.set_page_names(names.as_slice().into());
+}
+
 fn install_test_callback(window: &MainWindow) {
     let _ = window.as_weak();
     window.on_update_fact(move || {
@@ -114,10 +144,12 @@ fn install_test_callback(window: &MainWindow) {
 fn handle_events(
     window: &Weak<MainWindow>,
     rx: &Receiver<AppEvent>,
+    pages: &mut Vec<PageConfig>,
     status_list_items: &mut Vec<SharedString>,
 ) {
     if let Some(window) = window.upgrade() {
-        if let Ok(event) = rx.try_recv() {
+        // Everything queued since the last tick, so bursts like latch updates show at once
+        while let Ok(event) = rx.try_recv() {
             let text = match event {
                 AppEvent::WifiUpdate(status) => {
                     let text = match status {
@@ -168,6 +200,76 @@ fn handle_events(
                     // Return None since we don't want to add this to the UI logs
                     Some(SharedString::from(&status))
                 }
+                AppEvent::StatusMessage(message) => Some(SharedString::from(&message)),
+                AppEvent::ButtonLatched(button_id, latched) => {
+                    // Button IDs are 1-based, matching the grid's `button-pressed` callback
+                    let Ok(idx) = usize::try_from(button_id - 1) else {
+                        log::warn!("No button {} to latch", button_id);
+                        continue;
+                    };
+                    let mut states: Vec<bool> = window.get_button_latched().iter().collect();
+                    if states.len() <= idx {
+                        states.resize(idx + 1, false);
+                    }
+                    states[idx] = latched;
+                    window.set_button_latched(states.as_slice().into());
+                    // Return None since we don't want to add this to the UI logs
+                    None
+                }
+                AppEvent::PageChanged(page, latched_buttons) => {
+                    window.set_current_page(page as i32);
+                    set_button_names(
+                        &window,
+                        pages.get(page).and_then(|p| p.button_names.clone()),
+                    );
+                    let mut states: Vec<bool> = Vec::new();
+                    for idx in latched_buttons
+                        .into_iter()
+                        .filter_map(|button_id| usize::try_from(button_id - 1).ok())
+                    {
+                        if states.len() <= idx {
+                            states.resize(idx + 1, false);
+                        }
+                        states[idx] = true;
+                    }
+                    window.set_button_latched(states.as_slice().into());
+                    pages
+                        .get(page)
+                        .map(|p| SharedString::from(format!("Page: {}", p.name)))
+                }
+                AppEvent::MappingUpdated(new_pages) => {
+                    *pages = new_pages;
+                    set_page_names(&window, pages);
+                    // A page that went away is followed by a PageChanged from the actor
+                    let page = window.get_current_page() as usize;
+                    set_button_names(
+                        &window,
+                        pages.get(page).and_then(|p| p.button_names.clone()),
+                    );
+                    Some(SharedString::from("Mappings updated"))
+                }
+                AppEvent::ButtonLabelUpdate(button_id, label) => {
+                    // Button IDs are 1-based, matching the grid's `button-pressed` callback
+                    let Ok(idx) = usize::try_from(button_id - 1) else {
+                        log::warn!("No button {} to relabel", button_id);
+                        continue;
+                    };
+                    let mut names: Vec<SharedString> = window.get_button_names().iter().collect();
+                    match names.get_mut(idx) {
+                        Some(name) => {
+                            *name = SharedString::from(label.chars().take(20).collect::<String>());
+                            window.set_button_names(names.as_slice().into());
+                        }
+                        None => log::warn!("No button {} to relabel", button_id),
+                    }
+                    // Return None since we don't want to add this to the UI logs
+                    None
+                }
+                AppEvent::KeyboardLeds(leds) => {
+                    window.set_caps_lock(leds.caps_lock());
+                    // Return None since we don't want to add this to the UI logs
+                    None
+                }
                 AppEvent::ServerWidgetUpdate(data) => match data.id {
                     1 => {
                         window.set_server_widget_2_title(SharedString::from(&data.title));
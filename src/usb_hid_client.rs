@@ -2,8 +2,10 @@ use crate::{
     bsp::{
         usb::Usb,
         usb_desc::{
-            ConsumerReport, KeyboardReport, MouseReport, REPORT_ID_CONSUMER, REPORT_ID_KEYBOARD,
-            REPORT_ID_MOUSE,
+            AbsoluteMouseReport, ConsumerReport, GamepadReport, KeyboardReport, MouseReport,
+            NkroKeyboardReport, RawReport, SystemReport, REPORT_ID_ABSOLUTE_MOUSE,
+            REPORT_ID_CONSUMER, REPORT_ID_GAMEPAD, REPORT_ID_KEYBOARD, REPORT_ID_KEYBOARD_NKRO,
+            REPORT_ID_MOUSE, REPORT_ID_RAW, REPORT_ID_SYSTEM,
         },
     },
     events::{AppEvent, UsbHidCommand},
@@ -66,6 +68,37 @@ impl UsbHidClient {
                                     &report,
                                     size_of::<ConsumerReport>(),
                                 ),
+                                UsbHidCommand::SendKeyboardNkro(report) => Usb::send_hid_report(
+                                    TUSB_HID_ITF,
+                                    REPORT_ID_KEYBOARD_NKRO,
+                                    &report,
+                                    size_of::<NkroKeyboardReport>(),
+                                ),
+                                UsbHidCommand::SendAbsoluteMouse(report) => Usb::send_hid_report(
+                                    TUSB_HID_ITF,
+                                    REPORT_ID_ABSOLUTE_MOUSE,
+                                    &report,
+                                    size_of::<AbsoluteMouseReport>(),
+                                ),
+                                UsbHidCommand::SendSystem(report) => Usb::send_hid_report(
+                                    TUSB_HID_ITF,
+                                    REPORT_ID_SYSTEM,
+                                    &report,
+                                    size_of::<SystemReport>(),
+                                ),
+                                UsbHidCommand::SendGamepad(report) => Usb::send_hid_report(
+                                    TUSB_HID_ITF,
+                                    REPORT_ID_GAMEPAD,
+                                    &report,
+                                    size_of::<GamepadReport>(),
+                                ),
+                                UsbHidCommand::SendRaw(report) => Usb::send_hid_report(
+                                    TUSB_HID_ITF,
+                                    REPORT_ID_RAW,
+                                    &report,
+                                    size_of::<RawReport>(),
+                                ),
+                                UsbHidCommand::SendMidi(packet) => Usb::send_midi_packet(&packet),
                             };
                             if report_sent {
                                 log::info!(
@@ -40,7 +40,7 @@ export type ConfigActionMouseWheel = {
 
 export type ConfigActionConsumerPress = {
     type: 'ConsumerPress';
-    usage_id: number; // u16
+    usage_id: number | string; // u16, or a usage name such as 'VolumeIncrement'
 };
 
 export type ConfigActionConsumerRelease = { type: 'ConsumerRelease' };